@@ -1,15 +1,18 @@
-#![feature(try_blocks)]
 use std::path::PathBuf;
 
 use quote::{format_ident, quote};
 
+/// `Eval<'id, Self>` for every processor brand `'id`
 #[proc_macro_derive(Eval)]
 pub fn derive_eval_for_self(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = syn::parse_macro_input!(tokens as syn::DeriveInput);
     let name = &input.ident;
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut generics = input.generics.clone();
+    generics.params.insert(0, syn::parse_quote!('__id));
+    let (impl_generics, _, _) = generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
     let expanded = quote::quote! {
-        impl #impl_generics Eval<#name #ty_generics> for #name #ty_generics #where_clause {
+        impl #impl_generics Eval<'__id, #name #ty_generics> for #name #ty_generics #where_clause {
             #[inline]
             fn eval(self) -> #name #ty_generics {
                 self
@@ -21,20 +24,33 @@ pub fn derive_eval_for_self(tokens: proc_macro::TokenStream) -> proc_macro::Toke
 
 #[proc_macro]
 pub fn commands(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match load_commands(tokens) {
+        Ok(commands) => commands.into(),
+        Err(error) => {
+            let message = format!("failed to load the commands: {error}");
+            quote! { compile_error!(#message); }.into()
+        }
+    }
+}
+
+fn load_commands(
+    tokens: proc_macro::TokenStream,
+) -> Result<proc_macro2::TokenStream, Box<dyn std::error::Error>> {
     use std::fs;
-    let expanded: Result<_, Box<dyn std::error::Error>> = try {
-        let path = PathBuf::from(&std::env::var("CARGO_MANIFEST_DIR")?).join(
-            tokens
-                .to_string()
-                .trim_start_matches("\"")
-                .trim_end_matches("\""),
-        );
-        let json = serde_json::from_reader::<_, serde_json::Value>(
-            fs::File::open(&path).map_err(|e| format!("{e} at {}", path.to_string_lossy()))?,
-        )?;
-        enum_template("command", json.as_object().expect("invalid template"), 0)
-    };
-    expanded.unwrap().into()
+    let path = PathBuf::from(&std::env::var("CARGO_MANIFEST_DIR")?).join(
+        tokens
+            .to_string()
+            .trim_start_matches("\"")
+            .trim_end_matches("\""),
+    );
+    let json = serde_json::from_reader::<_, serde_json::Value>(
+        fs::File::open(&path).map_err(|e| format!("{e} at {}", path.to_string_lossy()))?,
+    )?;
+    Ok(enum_template(
+        "command",
+        json.as_object().expect("invalid template"),
+        0,
+    ))
 }
 
 fn enum_template(
@@ -47,6 +63,11 @@ fn enum_template(
     let mut variants = quote::quote! {};
     let mut writes = quote::quote! {};
     let mut froms = quote::quote! {};
+    let mut args = quote::quote! {};
+    let mut inputs = quote::quote! {};
+    let mut outputs = quote::quote! {};
+    let mut inputs_mut = quote::quote! {};
+    let mut outputs_mut = quote::quote! {};
     let mut sub_commands = quote::quote! {};
 
     let padding = map.get("padding").map(|padding| {
@@ -73,6 +94,11 @@ fn enum_template(
 
         variants.extend(quote::quote! { #command_uppercase(#command_ty), });
         writes.extend(quote::quote! { Self::#command_uppercase(sc) => sc.fmt(f), });
+        args.extend(quote::quote! { Self::#command_uppercase(sc) => sc.args(), });
+        inputs.extend(quote::quote! { Self::#command_uppercase(sc) => sc.inputs(), });
+        outputs.extend(quote::quote! { Self::#command_uppercase(sc) => sc.outputs(), });
+        inputs_mut.extend(quote::quote! { Self::#command_uppercase(sc) => sc.inputs_mut(), });
+        outputs_mut.extend(quote::quote! { Self::#command_uppercase(sc) => sc.outputs_mut(), });
         froms.extend(quote! {
             impl From<#command_ty> for #enum_name {
                 fn from(sc: #command_ty) -> Self {
@@ -105,6 +131,41 @@ fn enum_template(
             #variants
         }
 
+        impl #enum_name {
+            /// every argument, in the order they are written
+            pub fn args(&self) -> Vec<&crate::String> {
+                match self {
+                    #args
+                }
+            }
+
+            /// arguments read by the instruction
+            pub fn inputs(&self) -> Vec<&crate::String> {
+                match self {
+                    #inputs
+                }
+            }
+
+            /// variables written by the instruction
+            pub fn outputs(&self) -> Vec<&crate::String> {
+                match self {
+                    #outputs
+                }
+            }
+
+            pub fn inputs_mut(&mut self) -> Vec<&mut crate::String> {
+                match self {
+                    #inputs_mut
+                }
+            }
+
+            pub fn outputs_mut(&mut self) -> Vec<&mut crate::String> {
+                match self {
+                    #outputs_mut
+                }
+            }
+        }
+
         impl std::fmt::Display for #enum_name {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 #self_write
@@ -134,23 +195,62 @@ fn struct_template(
         .map(|padding| padding as usize - fields.len())
         .unwrap_or_default();
 
-    let writes = fields
+    // a field starting with `>` is written by the instruction, one starting with `#` is a
+    // keyword rather than an operand
+    let (fields, kinds): (Vec<_>, Vec<_>) = fields
         .iter()
         .map(|field| {
-            let field = format_ident!("{}", field.as_str().expect("invalid template"));
-            quote::quote! { write!(f, " {}", self.#field)?; }
+            let field = field.as_str().expect("invalid template");
+            let (kind, field) = field.split_at(field.starts_with(['>', '#']) as usize);
+            (format_ident!("{}", field), kind)
         })
-        .chain((0..padding).map(|_| quote::quote! {write!(f, " 0")?;}));
+        .unzip();
+    let with_kind = |kind: &str| {
+        fields
+            .iter()
+            .zip(&kinds)
+            .filter_map(|(field, k)| (*k == kind).then_some(field))
+            .collect::<Vec<_>>()
+    };
+    let inputs = with_kind("");
+    let outputs = with_kind(">");
 
-    let fields = fields
+    let writes = fields
         .iter()
-        .map(|field| format_ident!("{}", field.as_str().expect("invalid template")));
+        .map(|field| quote::quote! { write!(f, " {}", self.#field)?; })
+        .chain((0..padding).map(|_| quote::quote! {write!(f, " 0")?;}));
+
     let struct_define = quote::quote! {
         #[derive(Debug, Clone)]
         pub struct #struct_name {
             #(pub #fields: crate::String,)*
         }
 
+        impl #struct_name {
+            /// every argument, in the order they are written
+            pub fn args(&self) -> Vec<&crate::String> {
+                vec![#(&self.#fields,)*]
+            }
+
+            /// arguments read by the instruction
+            pub fn inputs(&self) -> Vec<&crate::String> {
+                vec![#(&self.#inputs,)*]
+            }
+
+            /// variables written by the instruction
+            pub fn outputs(&self) -> Vec<&crate::String> {
+                vec![#(&self.#outputs,)*]
+            }
+
+            pub fn inputs_mut(&mut self) -> Vec<&mut crate::String> {
+                vec![#(&mut self.#inputs,)*]
+            }
+
+            pub fn outputs_mut(&mut self) -> Vec<&mut crate::String> {
+                vec![#(&mut self.#outputs,)*]
+            }
+        }
+
         impl std::fmt::Display for #struct_name {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 write!(f, #command)?;
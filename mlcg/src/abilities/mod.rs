@@ -1,7 +1,11 @@
-use crate::{eval::Eval, String};
+use crate::types::Type;
 
 pub trait Shoot {}
 
-pub trait Target : Eval<String>{}
+/// what `targetp` and `shootp` aim at
+pub trait Target: Type {}
 
 pub trait Boost {}
+
+/// units which can build, with `ucontrol build`
+pub trait Build {}
@@ -0,0 +1,165 @@
+//! mistakes made while building a program, collected by the [`crate::processor::Processor`] and
+//! returned by [`crate::processor::Processor::generate`] instead of panicking
+
+use std::panic::Location;
+
+use crate::limits::{Report, SplitError};
+
+/// a misuse of the builder, or a program which can not be generated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// the values saved by a recursive function do not fit in its [`crate::function::Stack`]
+    FrameTooLarge { values: usize, capacity: usize },
+    /// [`crate::flow::If::else_`] is used after the closure which emitted its `if_` returned
+    ElseOutsideIf,
+    /// a closure building a block left the scopes of the builder unbalanced
+    UnbalancedScopes,
+    /// a label is jumped to but never placed, or a declared function is called but never defined
+    UnplacedLabel,
+    /// the program does not fit in the processor
+    LimitExceeded(Report),
+    /// the program does not fit in the processor, and can not be split
+    Split(SplitError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::FrameTooLarge { values, capacity } => write!(
+                f,
+                "a frame of {values} values does not fit in a stack of {capacity} slots"
+            ),
+            Error::ElseOutsideIf => write!(f, "`else_` used outside the scope of its `if_`"),
+            Error::UnbalancedScopes => write!(f, "the scopes of the builder are unbalanced"),
+            Error::UnplacedLabel => write!(
+                f,
+                "a label is used but never placed, or a function is called but never defined"
+            ),
+            Error::LimitExceeded(report) => {
+                writeln!(f, "the program exceeds the instruction limit")?;
+                report.fmt(f)
+            }
+            Error::Split(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// an [`Error`], with the call site of the builder which made it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub error: Error,
+    pub location: &'static Location<'static>,
+}
+
+impl Diagnostic {
+    /// `error` made by the caller of the builder, every function between the builder and this
+    /// one has to be `#[track_caller]`
+    #[track_caller]
+    pub fn new(error: Error) -> Self {
+        Self {
+            error,
+            location: Location::caller(),
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.error)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        processor::Processor,
+        types::{
+            building::{Building, MemoryCell},
+            number::Number,
+        },
+    };
+
+    #[test]
+    fn diagnostics() {
+        Processor::scope(|core| {
+            let cell = core.from_mdt::<Building<MemoryCell>>("cell1");
+            let message = core.from_mdt::<Building>("message1");
+            let stack = core.stack(cell, "sp", message);
+            let function = core.declare_recursive_function(&stack);
+            let line = line!() + 3;
+            function.define(|_, [n]| {
+                let sum = (0..64).fold(n, |sum, _| sum + n);
+                function.call((sum,))
+            });
+
+            let diagnostics = core.generate().unwrap_err();
+            assert_eq!(diagnostics.len(), 1);
+            assert!(matches!(
+                diagnostics[0].error,
+                Error::FrameTooLarge { capacity: 64, .. }
+            ));
+            assert_eq!(diagnostics[0].location.file(), file!());
+            assert_eq!(diagnostics[0].location.line(), line);
+            assert!(diagnostics[0]
+                .to_string()
+                .contains("values does not fit in a stack of 64 slots"));
+        });
+    }
+
+    #[test]
+    fn unplaced_labels() {
+        Processor::scope(|core| {
+            let line = line!() + 1;
+            let label = core.new_label();
+            core.goto(label);
+            let function = core.declare_function::<0>();
+            let _ = function.call(());
+
+            let diagnostics = core.generate().unwrap_err();
+            assert_eq!(diagnostics.len(), 2);
+            assert!(diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.error == Error::UnplacedLabel));
+            assert_eq!(diagnostics[0].location.line(), line);
+            assert_eq!(diagnostics[1].location.line(), line + 2);
+            // the passes do not need the labels to be placed
+            let _ = core.report();
+        });
+    }
+
+    #[test]
+    fn else_outside_if() {
+        Processor::scope(|core| {
+            let a = core.from_mdt::<Number>("a");
+            let mut escaped = None;
+            let _ = core.if_(a, |core| escaped = Some(core.if_(a, |_| {})));
+            let line = line!() + 1;
+            escaped.unwrap().else_(|_| {});
+
+            let diagnostics = core.generate().unwrap_err();
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].error, Error::ElseOutsideIf);
+            assert_eq!(diagnostics[0].location.line(), line);
+        });
+
+        // the `else_` would run before the code emitted after the `if`
+        Processor::scope(|core| {
+            let a = core.from_mdt::<Number>("a");
+            let unit = core.unit();
+            let i = core.if_(a, |_| unit.flag(1));
+            unit.flag(2);
+            let line = line!() + 1;
+            i.else_(|_| unit.flag(3));
+
+            let diagnostics = core.generate().unwrap_err();
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].error, Error::ElseOutsideIf);
+            assert_eq!(diagnostics[0].location.line(), line);
+        });
+    }
+}
@@ -1,17 +1,10 @@
 pub use mlcg_derive::*;
 
-use crate::processor::Processor;
-
-pub trait WithCore {
-    fn core(&self) -> Option<&Processor>;
-}
-
-impl<T> WithCore for T {
-    default fn core(&self) -> Option<&Processor> {
-        None
-    }
-}
-
-pub trait Eval<T>: WithCore {
+/// a value converted to `T` when used as an argument of an instruction
+///
+/// `'id` is the brand of the [`crate::processor::Processor`] the value belongs to: a
+/// [`crate::r#ref::Ref`] only implements it for its own processor, so passing a variable of
+/// another processor does not compile, while immediates implement it for every processor
+pub trait Eval<'id, T> {
     fn eval(self) -> T;
 }
@@ -0,0 +1,449 @@
+use crate::{
+    eval::Eval,
+    processor::{Block, Label, Processor, Statement},
+    r#ref::Ref,
+    types::{boolean::Bool, number::Number},
+    Error, String,
+};
+
+/// condition of a `jump`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Condition {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanEq,
+    GreaterThan,
+    GreaterThanEq,
+    StrictEqual,
+    Always,
+}
+
+impl Condition {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Condition::Equal => "equal",
+            Condition::NotEqual => "notEqual",
+            Condition::LessThan => "lessThan",
+            Condition::LessThanEq => "lessThanEq",
+            Condition::GreaterThan => "greaterThan",
+            Condition::GreaterThanEq => "greaterThanEq",
+            Condition::StrictEqual => "strictEqual",
+            Condition::Always => "always",
+        }
+    }
+
+    /// parse a condition from its name, comparison `op`s share the names of conditions
+    pub fn from_name(name: &str) -> Option<Self> {
+        let condition = match name {
+            "equal" => Condition::Equal,
+            "notEqual" => Condition::NotEqual,
+            "lessThan" => Condition::LessThan,
+            "lessThanEq" => Condition::LessThanEq,
+            "greaterThan" => Condition::GreaterThan,
+            "greaterThanEq" => Condition::GreaterThanEq,
+            "strictEqual" => Condition::StrictEqual,
+            "always" => Condition::Always,
+            _ => return None,
+        };
+        Some(condition)
+    }
+
+    /// the condition that holds exactly when `self` does not
+    ///
+    /// `strictEqual` and `always` have no negated form in mlog
+    pub fn negate(&self) -> Option<Self> {
+        let negated = match self {
+            Condition::Equal => Condition::NotEqual,
+            Condition::NotEqual => Condition::Equal,
+            Condition::LessThan => Condition::GreaterThanEq,
+            Condition::LessThanEq => Condition::GreaterThan,
+            Condition::GreaterThan => Condition::LessThanEq,
+            Condition::GreaterThanEq => Condition::LessThan,
+            Condition::StrictEqual | Condition::Always => return None,
+        };
+        Some(negated)
+    }
+}
+
+impl Eval<'_, String> for Condition {
+    fn eval(self) -> String {
+        String::Static(self.name())
+    }
+}
+
+impl<'id> Processor<'id> {
+    /// create a [`Label`], place it with [`Processor::place_label`]
+    #[track_caller]
+    pub fn new_label(&self) -> Label<'id> {
+        Label::new(self.borrow_mut().alloc_label())
+    }
+
+    /// place `label` before the next emitted instruction
+    pub fn place_label(&self, label: Label<'id>) {
+        self.borrow_mut().push_statement(Statement::Label(label.id));
+    }
+
+    /// create a [`Label`] placed before the next emitted instruction
+    #[track_caller]
+    pub fn label(&self) -> Label<'id> {
+        let label = self.new_label();
+        self.place_label(label);
+        label
+    }
+
+    pub fn jump(
+        &self,
+        target: Label<'id>,
+        condition: Condition,
+        lhs: impl Eval<'id, String>,
+        rhs: impl Eval<'id, String>,
+    ) {
+        let statement = Statement::Jump {
+            target: target.id,
+            condition,
+            lhs: lhs.eval(),
+            rhs: rhs.eval(),
+        };
+        self.borrow_mut().push_statement(statement);
+    }
+
+    #[doc(alias = "jump")]
+    pub fn goto(&self, target: Label<'id>) {
+        self.jump(target, Condition::Always, 0, 0);
+    }
+
+    #[doc(alias = "jump")]
+    pub fn jump_if(&self, target: Label<'id>, condition: impl Eval<'id, Bool>) {
+        self.jump(target, Condition::NotEqual, condition.eval(), false);
+    }
+
+    /// collect everything `body` emits into a new [`Block`]
+    #[track_caller]
+    pub(crate) fn scoped(&'id self, body: impl FnOnce(&'id Processor<'id>)) -> Block {
+        self.borrow_mut().scopes.push(Block::default());
+        body(self);
+        let block = self.borrow_mut().scopes.pop();
+        block.unwrap_or_else(|| {
+            self.error(Error::UnbalancedScopes);
+            Block::default()
+        })
+    }
+
+    /// run `then` when `condition`, a [`Bool`] or any number, is not `0`/`null`/`false`
+    ///
+    /// a comparison computed right before is fused into the `jump`
+    pub fn if_(
+        &'id self,
+        condition: impl Eval<'id, Number>,
+        then: impl FnOnce(&'id Processor<'id>),
+    ) -> If<'id> {
+        let condition = condition.eval().eval();
+        let then = self.scoped(then);
+        let mut raw = self.borrow_mut();
+        let statement = Statement::If {
+            condition,
+            then,
+            otherwise: Block::default(),
+            otherwise_label: raw.alloc_label(),
+            end_label: raw.alloc_label(),
+        };
+        raw.push_statement(statement);
+        let depth = raw.scopes.len();
+        let index = raw.current_block().statements.len() - 1;
+        If {
+            core: self,
+            depth,
+            index,
+        }
+    }
+
+    /// run `body` forever, until it breaks out with [`Loop::break_`]
+    pub fn loop_(&'id self, body: impl FnOnce(&'id Processor<'id>, &Loop<'id>)) {
+        let control = Loop {
+            core: self,
+            continue_label: self.label(),
+            break_label: self.new_label(),
+        };
+        body(self, &control);
+        self.goto(control.continue_label);
+        self.place_label(control.break_label);
+    }
+
+    /// run `body` while the value computed by `condition` holds
+    ///
+    /// `condition` is emitted before every iteration, so it sees the changes made by `body`
+    pub fn while_<C: Eval<'id, Number>>(
+        &'id self,
+        condition: impl FnOnce(&'id Processor<'id>) -> C,
+        body: impl FnOnce(&'id Processor<'id>, &Loop<'id>),
+    ) {
+        let control = Loop {
+            core: self,
+            continue_label: self.label(),
+            break_label: self.new_label(),
+        };
+        let condition = condition(self);
+        let _ = self.if_(condition, |core| {
+            body(core, &control);
+            core.goto(control.continue_label);
+        });
+        self.place_label(control.break_label);
+    }
+
+    /// run `body` with a counter going from `start` (inclusive) to `end` (exclusive) by `step`
+    ///
+    /// the counter counts down when `step` is negative, up otherwise. the direction of a `step`
+    /// which is not an immediate is only known when running, so the loop exits once the counter is
+    /// past `end` in the direction of `step`, and right away if `step` is `0`
+    pub fn for_range(
+        &'id self,
+        start: impl Eval<'id, Number>,
+        end: impl Eval<'id, Number>,
+        step: impl Eval<'id, Number>,
+        body: impl FnOnce(&'id Processor<'id>, Ref<'id, Number>, &Loop<'id>),
+    ) {
+        let end: String = end.eval().eval();
+        let step: Number = step.eval();
+
+        let mut counter = self.new_unnamed::<Number>().set_to(start);
+        let head = self.label();
+        let control = Loop {
+            core: self,
+            continue_label: self.new_label(),
+            break_label: self.new_label(),
+        };
+        match &step {
+            Number::Immediate(step) if *step < 0.0 => {
+                self.jump(control.break_label, Condition::LessThanEq, counter, end);
+            }
+            Number::Immediate(_) => {
+                self.jump(control.break_label, Condition::GreaterThanEq, counter, end);
+            }
+            // `counter - end` has the sign of `step` once the counter is past `end`
+            Number::Variable(step) => {
+                let past = (counter - Number::Variable(end)) * Number::Variable(step.clone());
+                self.jump(control.break_label, Condition::GreaterThanEq, past, 0);
+            }
+        }
+        body(self, counter, &control);
+        self.place_label(control.continue_label);
+        counter += step;
+        self.goto(head);
+        self.place_label(control.break_label);
+    }
+}
+
+/// the loop being built, created by [`Processor::loop_`], [`Processor::while_`] and
+/// [`Processor::for_range`]
+pub struct Loop<'a> {
+    core: &'a Processor<'a>,
+    continue_label: Label<'a>,
+    break_label: Label<'a>,
+}
+
+impl Loop<'_> {
+    /// jump out of the loop
+    #[doc(alias = "break")]
+    pub fn break_(&self) {
+        self.core.goto(self.break_label);
+    }
+
+    /// jump to the next iteration of the loop
+    #[doc(alias = "continue")]
+    pub fn continue_(&self) {
+        self.core.goto(self.continue_label);
+    }
+}
+
+/// an emitted `if`, created by [`Processor::if_`]
+///
+/// [`If::else_`] must come right after the `if`, before anything else is emitted in its scope
+#[must_use = "an `if` without `else_` can be discarded with `let _ =`"]
+pub struct If<'a> {
+    core: &'a Processor<'a>,
+    depth: usize,
+    index: usize,
+}
+
+impl<'a> If<'a> {
+    /// run `otherwise` when the condition does not hold
+    #[track_caller]
+    pub fn else_(self, otherwise: impl FnOnce(&'a Processor<'a>)) {
+        let block = self.core.scoped(otherwise);
+        let mut raw = self.core.borrow_mut();
+        let statement = match self.depth <= raw.scopes.len() {
+            true => {
+                let statements = &mut raw.block_at(self.depth).statements;
+                // anything emitted after the `if` would run before the `else_`
+                match statements.len() == self.index + 1 {
+                    true => statements.get_mut(self.index),
+                    false => None,
+                }
+            }
+            false => None,
+        };
+        match statement {
+            Some(Statement::If { otherwise, .. }) => *otherwise = block,
+            _ => {
+                drop(raw);
+                self.core.error(Error::ElseOutsideIf);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::PassManager;
+
+    #[test]
+    fn jump() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new());
+            let a = core.from_mdt::<Number>("a");
+
+            let head = core.label();
+            let end = core.new_label();
+            core.jump(end, Condition::GreaterThanEq, a, 10);
+            let _ = a + 1;
+            core.goto(head);
+            core.place_label(end);
+            let _ = a + 2;
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "jump 3 greaterThanEq a 10\n\
+                 op add v0 a 1\n\
+                 jump 0 always 0 0\n\
+                 op add v1 a 2\n"
+            );
+        });
+    }
+
+    #[test]
+    fn if_else() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new());
+            let a = core.from_mdt::<Number>("a");
+
+            core.if_(a, |_| {
+                let _ = a + 1;
+            })
+            .else_(|_| {
+                let _ = a + 2;
+            });
+            let _ = core.if_(a.lt(10), |core| {
+                let _ = core.if_(a, |_| {
+                    let _ = a + 3;
+                });
+            });
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "jump 3 equal a false\n\
+                 op add v0 a 1\n\
+                 jump 4 always 0 0\n\
+                 op add v1 a 2\n\
+                 jump 0 greaterThanEq a 10\n\
+                 jump 0 equal a false\n\
+                 op add v3 a 3\n"
+            );
+        });
+    }
+
+    #[test]
+    fn if_keeps_used_condition() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new());
+            let a = core.from_mdt::<Number>("a");
+
+            let lt = a.lt(10);
+            let _ = core.if_(lt, |_| {
+                let _ = a + lt;
+            });
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "op lessThan v0 a 10\n\
+                 jump 0 equal v0 false\n\
+                 op add v1 a v0\n"
+            );
+        });
+    }
+
+    #[test]
+    fn loops() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new());
+            let a = core.from_mdt::<Number>("a");
+
+            // code around the loops, so that no jump targets the start or the end
+            core.unit().flag(a);
+            core.loop_(|core, outer| {
+                core.while_(
+                    |_| a.lt(10),
+                    |core, inner| {
+                        let _ = core.if_(a, |_| inner.continue_());
+                        outer.break_();
+                    },
+                );
+            });
+            core.unit().flag(0);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "ucontrol flag a 0 0 0 0\n\
+                 jump 6 greaterThanEq a 10\n\
+                 jump 4 equal a false\n\
+                 jump 1 always 0 0\n\
+                 jump 7 always 0 0\n\
+                 jump 1 always 0 0\n\
+                 jump 1 always 0 0\n\
+                 ucontrol flag 0 0 0 0 0\n"
+            );
+        });
+    }
+
+    #[test]
+    fn for_range() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new());
+            let a = core.from_mdt::<Number>("a");
+
+            core.unit().flag(a);
+            core.for_range(0, a, 2, |core, i, _| {
+                core.for_range(i, 0, -1, |_, j, _| {
+                    let _ = i * j;
+                });
+            });
+            core.for_range(10, 0, a, |core, i, _| {
+                core.unit().flag(i);
+            });
+            core.unit().flag(0);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "ucontrol flag a 0 0 0 0\n\
+                 set v0 0\n\
+                 jump 10 greaterThanEq v0 a\n\
+                 set v1 v0\n\
+                 jump 8 lessThanEq v1 0\n\
+                 op mul v2 v0 v1\n\
+                 op add v1 v1 -1\n\
+                 jump 4 always 0 0\n\
+                 op add v0 v0 2\n\
+                 jump 2 always 0 0\n\
+                 set v3 10\n\
+                 op sub v4 v3 0\n\
+                 op mul v5 v4 a\n\
+                 jump 17 greaterThanEq v5 0\n\
+                 ucontrol flag v3 0 0 0 0\n\
+                 op add v3 v3 a\n\
+                 jump 11 always 0 0\n\
+                 ucontrol flag 0 0 0 0 0\n"
+            );
+        });
+    }
+}
@@ -0,0 +1,463 @@
+use crate::{
+    command,
+    eval::Eval,
+    flow::Condition,
+    processor::{Label, Processor, Statement},
+    r#ref::Ref,
+    types::{
+        building::{Building, Memory},
+        number::Number,
+        Type,
+    },
+    Error, String,
+};
+
+/// arguments of a [`Function`] of the processor `'id` with `N` parameters, implemented for tuples
+pub trait Arguments<'id, const N: usize> {
+    fn eval_all(self) -> [Number; N];
+}
+
+macro_rules! arguments_impl {
+    ($($n:literal => ($($arg:ident),*);)*) => {$(
+        impl<'id, $($arg: Eval<'id, Number>),*> Arguments<'id, $n> for ($($arg,)*) {
+            #[allow(non_snake_case)]
+            fn eval_all(self) -> [Number; $n] {
+                let ($($arg,)*) = self;
+                [$($arg.eval()),*]
+            }
+        }
+    )*};
+}
+
+arguments_impl! {
+    0 => ();
+    1 => (A);
+    2 => (A, B);
+    3 => (A, B, C);
+    4 => (A, B, C, D);
+    5 => (A, B, C, D, E);
+    6 => (A, B, C, D, E, F);
+    7 => (A, B, C, D, E, F, G);
+    8 => (A, B, C, D, E, F, G, H);
+}
+
+/// value returned from a [`Function`] of the processor `'id`, `()` returns nothing
+pub trait ReturnValue<'id>: Sized {
+    fn value(self) -> Option<String>;
+}
+
+impl ReturnValue<'_> for () {
+    fn value(self) -> Option<String> {
+        None
+    }
+}
+
+impl<'id, T: Type> ReturnValue<'id> for Ref<'id, T> {
+    fn value(self) -> Option<String> {
+        Some(self.eval())
+    }
+}
+
+impl ReturnValue<'_> for Number {
+    fn value(self) -> Option<String> {
+        Some(self.eval())
+    }
+}
+
+macro_rules! return_value_impl {
+    ($($ty:ty),*) => {$(
+        impl ReturnValue<'_> for $ty {
+            fn value(self) -> Option<String> {
+                Eval::<Number>::eval(self).value()
+            }
+        }
+    )*};
+}
+
+return_value_impl!(f64, f32, i32, u32, i16, u16, i8, u8, bool);
+
+/// a subroutine with `N` parameters, emitted once after `main` and entered with `jump`
+///
+/// the caller stores its return address, the callee returns with `set @counter`. functions
+/// declared with a [`Stack`] may recurse: calls made from their body save the frame of the caller
+/// on the stack
+pub struct Function<'a, const N: usize> {
+    core: &'a Processor<'a>,
+    entry: Label<'a>,
+    parameters: [Ref<'a, Number>; N],
+    return_address: Ref<'a, Number>,
+    result: Ref<'a, Number>,
+    stack: Option<Stack<'a>>,
+}
+
+/// the function whose body is being built
+#[derive(Debug)]
+pub(crate) struct Frame {
+    /// return address and parameters
+    saved: Vec<String>,
+    /// variables created from this index on belong to the function
+    start: usize,
+    recursive: bool,
+}
+
+impl<'id> Processor<'id> {
+    /// create a [`Function`] without body, so it can be called before [`Function::define`]
+    #[track_caller]
+    pub fn declare_function<const N: usize>(&'id self) -> Function<'id, N> {
+        Function {
+            core: self,
+            entry: self.new_label(),
+            parameters: std::array::from_fn(|_| self.new_unnamed()),
+            return_address: self.new_unnamed(),
+            result: self.new_unnamed(),
+            stack: None,
+        }
+    }
+
+    /// create a [`Function`] and define its body
+    pub fn function<const N: usize, R: ReturnValue<'id>>(
+        &'id self,
+        body: impl FnOnce(&'id Processor<'id>, [Ref<'id, Number>; N]) -> R,
+    ) -> Function<'id, N> {
+        let function = self.declare_function();
+        function.define(body);
+        function
+    }
+
+    /// create a [`Function`] which saves frames on `stack`, without body, so it can call itself
+    #[track_caller]
+    pub fn declare_recursive_function<const N: usize>(
+        &'id self,
+        stack: &Stack<'id>,
+    ) -> Function<'id, N> {
+        Function {
+            stack: Some(stack.clone()),
+            ..self.declare_function()
+        }
+    }
+
+    /// create a [`Function`] which saves frames on `stack` and define its body
+    pub fn recursive_function<const N: usize, R: ReturnValue<'id>>(
+        &'id self,
+        stack: &Stack<'id>,
+        body: impl FnOnce(&'id Processor<'id>, [Ref<'id, Number>; N]) -> R,
+    ) -> Function<'id, N> {
+        let function = self.declare_recursive_function(stack);
+        function.define(body);
+        function
+    }
+}
+
+impl<'a, const N: usize> Function<'a, N> {
+    /// emit the body of the function, the value returned by `body` is returned to the caller
+    pub fn define<R: ReturnValue<'a>>(
+        &self,
+        body: impl FnOnce(&'a Processor<'a>, [Ref<'a, Number>; N]) -> R,
+    ) {
+        let frame = Frame {
+            saved: std::iter::once(&self.return_address)
+                .chain(&self.parameters)
+                .map(|variable| variable.eval())
+                .collect(),
+            start: self.core.borrow().variables.len(),
+            recursive: self.stack.is_some(),
+        };
+        self.core.borrow_mut().frames.push(frame);
+        let block = self.core.scoped(|_| {
+            self.core.place_label(self.entry);
+            let value = body(self.core, self.parameters);
+            self.return_(value);
+        });
+        let mut raw = self.core.borrow_mut();
+        raw.frames.pop();
+        raw.appends.push(block);
+    }
+
+    /// return from the function, can be used in its body for an early return
+    #[doc(alias = "return")]
+    pub fn return_(&self, value: impl ReturnValue<'a>) {
+        if let Some(value) = value.value() {
+            let command = command::set::Set {
+                result: self.result.eval(),
+                value,
+            };
+            self.core.borrow_mut().push_command(command);
+        }
+        self.core.counter().set_to(self.return_address);
+    }
+
+    /// the variables of the recursive function being built, which a call to `self` would clobber
+    ///
+    /// every variable created in the body so far is saved, named locals as well as temporaries,
+    /// except the ones marked by [`Ref::keep_alive`] which are shared with the outside
+    fn caller_frame(&self) -> Vec<String> {
+        let raw = self.core.borrow();
+        let Some(frame) = raw.frames.last().filter(|frame| frame.recursive) else {
+            return vec![];
+        };
+        if self.stack.is_none() {
+            return vec![];
+        }
+        let mut saved = frame.saved.clone();
+        for variable in &raw.variables[frame.start..] {
+            if !raw.kept.contains(variable) && !saved.contains(variable) {
+                saved.push(variable.clone());
+            }
+        }
+        saved
+    }
+
+    /// call the function, the returned value is copied into a new variable
+    #[track_caller]
+    pub fn call(&self, arguments: impl Arguments<'a, N>) -> Ref<'a, Number> {
+        let saved = self.caller_frame();
+        if let Some(stack) = &self.stack {
+            stack.push(&saved);
+        }
+
+        let mut arguments = arguments.eval_all();
+        // an argument read from a parameter must be read before that parameter is overwritten
+        for (index, argument) in arguments.iter_mut().enumerate() {
+            let Number::Variable(name) = argument else {
+                continue;
+            };
+            if self.parameters[..index]
+                .iter()
+                .any(|parameter| Eval::<String>::eval(*parameter) == *name)
+            {
+                let copy = self
+                    .core
+                    .new_unnamed::<Number>()
+                    .set_to(Number::Variable(name.clone()));
+                *argument = copy.eval();
+            }
+        }
+        for (parameter, argument) in self.parameters.iter().zip(arguments) {
+            parameter.set_to(argument);
+        }
+
+        let back = self.core.new_label();
+        let statement = Statement::Address {
+            result: self.return_address.eval(),
+            label: back.id,
+        };
+        self.core.borrow_mut().push_statement(statement);
+        self.core.goto(self.entry);
+        self.core.place_label(back);
+        let result = self.core.new_unnamed::<Number>().set_to(self.result);
+
+        if let Some(stack) = &self.stack {
+            stack.pop(&saved);
+        }
+        result
+    }
+}
+
+/// a stack in a memory cell or bank, used by recursive functions to save their frames
+///
+/// the pointer is the index of the next free slot, a push that would overflow prints an error to
+/// a message block and ends the program, which resets the pointer
+#[derive(Clone)]
+pub struct Stack<'a> {
+    core: &'a Processor<'a>,
+    memory: String,
+    capacity: usize,
+    pointer: Ref<'a, Number>,
+    overflow: Label<'a>,
+}
+
+impl<'id> Processor<'id> {
+    /// create a [`Stack`] in `memory`, with `pointer` as the name of the stack pointer
+    pub fn stack<M: Memory>(
+        &'id self,
+        memory: Ref<'id, Building<M>>,
+        pointer: impl Eval<'id, String>,
+        message: Ref<'id, Building>,
+    ) -> Stack<'id> {
+        let pointer = self.new_uninit::<Number>(pointer).set_to(0);
+        let overflow = self.new_label();
+        let message: String = message.eval();
+        let handler = self.scoped(|core| {
+            core.place_label(overflow);
+            let mut raw = core.borrow_mut();
+            raw.push_command(command::print::Print {
+                text: String::Static("\"stack overflow\""),
+            });
+            raw.push_command(command::printflush::Printflush { to: message });
+            raw.push_command(command::end::End {});
+        });
+        self.borrow_mut().appends.push(handler);
+        Stack {
+            core: self,
+            memory: memory.eval(),
+            capacity: M::CAPACITY,
+            pointer,
+            overflow,
+        }
+    }
+}
+
+impl Stack<'_> {
+    #[track_caller]
+    fn push(&self, values: &[String]) {
+        if values.is_empty() {
+            return;
+        }
+        if values.len() > self.capacity {
+            self.core.error(Error::FrameTooLarge {
+                values: values.len(),
+                capacity: self.capacity,
+            });
+            return;
+        }
+        self.core.jump(
+            self.overflow,
+            Condition::GreaterThan,
+            self.pointer,
+            self.capacity - values.len(),
+        );
+        // the pointer is a variable, bumped after each write rather than counted in rust
+        for value in values {
+            let command = command::write::Write {
+                src: value.clone(),
+                to: self.memory.clone(),
+                at: self.pointer.eval(),
+            };
+            self.core.borrow_mut().push_command(command);
+            self.pointer.binary_op_assign("add", 1);
+        }
+    }
+
+    fn pop(&self, values: &[String]) {
+        for value in values.iter().rev() {
+            self.pointer.binary_op_assign("sub", 1);
+            let command = command::read::Read {
+                dst: value.clone(),
+                from: self.memory.clone(),
+                at: self.pointer.eval(),
+            };
+            self.core.borrow_mut().push_command(command);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::PassManager;
+    use crate::types::building::MemoryCell;
+
+    use super::*;
+
+    #[test]
+    fn call() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new());
+            let a = core.from_mdt::<Number>("a");
+
+            let add = core.function(|_, [x, y]| x + y);
+            let b = add.call((a, 1));
+            let _ = add.call((b, a));
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "set v0 a\n\
+                 set v1 1\n\
+                 set v2 4\n\
+                 jump 11 always 0 0\n\
+                 set v5 v3\n\
+                 set v0 v5\n\
+                 set v1 a\n\
+                 set v2 9\n\
+                 jump 11 always 0 0\n\
+                 set v6 v3\n\
+                 end\n\
+                 op add v4 v0 v1\n\
+                 set v3 v4\n\
+                 set @counter v2\n"
+            );
+        });
+    }
+
+    #[test]
+    fn recursion() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new());
+            let cell = core.from_mdt::<Building<MemoryCell>>("cell1");
+            let message = core.from_mdt::<Building>("message1");
+            let stack = core.stack(cell, "sp", message);
+
+            let sum = core.declare_recursive_function(&stack);
+            sum.define(|core, [n]| {
+                let _ = core.if_(n, |_| {
+                    let rest = sum.call((n - 1,));
+                    sum.return_(rest + n);
+                });
+                0
+            });
+            let _ = sum.call((10,));
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "set sp 0\n\
+                 set v0 10\n\
+                 set v1 4\n\
+                 jump 9 always 0 0\n\
+                 set v6 v2\n\
+                 end\n\
+                 print \"stack overflow\"\n\
+                 printflush message1\n\
+                 end\n\
+                 jump 31 equal v0 false\n\
+                 op sub v3 v0 1\n\
+                 jump 6 greaterThan sp 61\n\
+                 write v1 cell1 sp\n\
+                 op add sp sp 1\n\
+                 write v0 cell1 sp\n\
+                 op add sp sp 1\n\
+                 write v3 cell1 sp\n\
+                 op add sp sp 1\n\
+                 set v0 v3\n\
+                 set v1 21\n\
+                 jump 9 always 0 0\n\
+                 set v4 v2\n\
+                 op sub sp sp 1\n\
+                 read v3 cell1 sp\n\
+                 op sub sp sp 1\n\
+                 read v0 cell1 sp\n\
+                 op sub sp sp 1\n\
+                 read v1 cell1 sp\n\
+                 op add v5 v4 v0\n\
+                 set v2 v5\n\
+                 set @counter v1\n\
+                 set v2 0\n\
+                 set @counter v1\n"
+            );
+        });
+    }
+
+    #[test]
+    fn recursion_locals() {
+        Processor::scope(|core| {
+            let cell = core.from_mdt::<Building<MemoryCell>>("cell1");
+            let message = core.from_mdt::<Building>("message1");
+            let stack = core.stack(cell, "sp", message);
+
+            let sum = core.declare_recursive_function(&stack);
+            sum.define(|core, [n]| {
+                let doubled = core.new_uninit::<Number>("doubled").set_to(n * 2);
+                let _ = core.if_(n, |_| {
+                    let rest = sum.call((n - 1,));
+                    sum.return_(rest + doubled);
+                });
+                0
+            });
+            core.unit().flag(sum.call((10,)));
+
+            let output = core.generate().unwrap();
+            // the local is written before the self call and read back after it
+            let write = output.find("write doubled cell1 sp").unwrap();
+            let read = output.find("read doubled cell1 sp").unwrap();
+            assert!(write < read);
+        });
+    }
+}
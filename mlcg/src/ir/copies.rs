@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use super::{Instruction, Operand, Pass, Program};
+use crate::String;
+
+/// `@` variables which keep their value while the processor runs
+const STABLE: &[&str] = &[
+    "@this", "@thisx", "@thisy", "@mapw", "@maph", "@server", "@client",
+];
+
+/// `true` if the value of `operand` may change without an instruction of the program writing
+/// it: most `@` variables, like `@time` or `@unit` which `ubind` changes, and kept variables
+pub(crate) fn is_volatile(kept: &HashSet<String>, operand: &Operand) -> bool {
+    operand.as_variable().is_some_and(|name| {
+        name.starts_with('@') && !STABLE.contains(&&**name) || kept.contains(name)
+    })
+}
+
+/// forget what is known about the variables `instruction` writes
+pub(crate) fn invalidate<T>(
+    instruction: &Instruction,
+    known: &mut Vec<T>,
+    mentions: impl Fn(&T, &String) -> bool,
+) {
+    for output in instruction.outputs() {
+        if let Some(name) = output.as_variable() {
+            known.retain(|known| !mentions(known, name));
+        }
+    }
+}
+
+/// read the source of a copy rather than the copy, while neither of them is written
+///
+/// copies are tracked within a block, from the `set`s made by
+/// [`crate::r#ref::Ref::set_to`] and [`crate::r#ref::Ref::save_as`]. the copy itself is removed
+/// by [`super::DeadCodeElimination`] once nothing reads it
+#[derive(Debug, Default)]
+pub struct CopyPropagation;
+
+impl Pass for CopyPropagation {
+    fn name(&self) -> &'static str {
+        "copy-propagation"
+    }
+
+    fn run(&mut self, program: &mut Program) -> bool {
+        let mut changed = false;
+        for block in &mut program.blocks {
+            // copy and source
+            let mut copies: Vec<(String, Operand)> = vec![];
+            for instruction in &mut block.instructions {
+                for input in instruction.inputs_mut() {
+                    let source = input.as_variable().and_then(|name| {
+                        let copies = copies.iter();
+                        copies.rev().find(|(copy, _)| copy == name)
+                    });
+                    if let Some((_, source)) = source {
+                        *input = source.clone();
+                        changed = true;
+                    }
+                }
+                invalidate(instruction, &mut copies, |(copy, source), name| {
+                    copy == name || source.as_variable() == Some(name)
+                });
+                let Some((result, value)) = instruction.as_set() else {
+                    continue;
+                };
+                if !is_volatile(&program.kept, result)
+                    && !is_volatile(&program.kept, value)
+                    && result != value
+                {
+                    copies.push((result.arg().clone(), value.clone()));
+                }
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::{CopyPropagation, PassManager},
+        processor::Processor,
+        types::number::Number,
+    };
+
+    #[test]
+    fn copies() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new().with(CopyPropagation));
+            let a = core.from_mdt::<Number>("a");
+
+            let b = core.new_uninit::<Number>("b").set_to(a);
+            let c = core.new_uninit::<Number>("c").set_to(b);
+            core.unit().r#move(b, c);
+            // `a` is written, so `b` and `c` no longer copy it
+            let _ = a.set_to(1);
+            core.unit().r#move(b, c);
+            let tick = core.new_uninit::<Number>("tick").set_to(core.tick());
+            core.unit().r#move(tick, tick);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "set b a\n\
+                 set c a\n\
+                 ucontrol move a a 0 0 0\n\
+                 set a 1\n\
+                 ucontrol move b c 0 0 0\n\
+                 set tick @tick\n\
+                 ucontrol move tick tick 0 0 0\n"
+            );
+        });
+    }
+}
@@ -0,0 +1,120 @@
+use super::{
+    copies::{invalidate, is_volatile},
+    Instruction, Operand, Pass, Program,
+};
+
+/// an `op` computed earlier in the block, with its result
+struct Expression {
+    op: String,
+    lhs: Operand,
+    rhs: Operand,
+    result: Operand,
+}
+
+impl Expression {
+    fn mentions(&self, name: &crate::String) -> bool {
+        [&self.lhs, &self.rhs, &self.result]
+            .into_iter()
+            .any(|operand| operand.as_variable() == Some(name))
+    }
+}
+
+/// replace an `op` computed earlier in the same block by a copy of the earlier result, as long as
+/// neither its operands nor that result were written in between
+///
+/// `rand` and `op`s reading variables whose value may change on its own, like `@time`, are never
+/// reused
+#[derive(Debug, Default)]
+pub struct CommonSubexpressionElimination;
+
+impl Pass for CommonSubexpressionElimination {
+    fn name(&self) -> &'static str {
+        "common-subexpression-elimination"
+    }
+
+    fn run(&mut self, program: &mut Program) -> bool {
+        let mut changed = false;
+        for block in &mut program.blocks {
+            let mut available: Vec<Expression> = vec![];
+            for instruction in &mut block.instructions {
+                let expression = instruction
+                    .as_op()
+                    .map(|(op, result, lhs, rhs)| Expression {
+                        op: op.to_owned(),
+                        lhs: lhs.clone(),
+                        rhs: rhs.clone(),
+                        result: result.clone(),
+                    });
+                let earlier = expression.as_ref().and_then(|expression| {
+                    available.iter().find(|earlier| {
+                        (&earlier.op, &earlier.lhs, &earlier.rhs)
+                            == (&expression.op, &expression.lhs, &expression.rhs)
+                    })
+                });
+                if let Some(earlier) = earlier {
+                    let result = expression.as_ref().unwrap().result.clone();
+                    if result != earlier.result {
+                        *instruction = Instruction::set(result, earlier.result.clone());
+                        changed = true;
+                    }
+                }
+
+                invalidate(instruction, &mut available, |expression, name| {
+                    expression.mentions(name)
+                });
+                let Some(expression) = expression.filter(|_| instruction.as_op().is_some()) else {
+                    continue;
+                };
+                let reused = expression.op != "rand"
+                    && !is_volatile(&program.kept, &expression.lhs)
+                    && !is_volatile(&program.kept, &expression.rhs)
+                    && !is_volatile(&program.kept, &expression.result)
+                    && expression.result != expression.lhs
+                    && expression.result != expression.rhs;
+                if reused {
+                    available.push(expression);
+                }
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::{CommonSubexpressionElimination, PassManager},
+        processor::Processor,
+        types::number::Number,
+    };
+
+    #[test]
+    fn cse() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new().with(CommonSubexpressionElimination));
+            let offset = core.from_mdt::<Number>("offset");
+
+            let x = core.thisx() + offset;
+            let y = core.from_mdt::<Number>("y");
+            let sum = y + offset;
+            core.unit().r#move(sum, y + offset);
+            core.unit().r#move(x, core.thisx() + offset);
+            let mut y = y;
+            y += 1;
+            core.unit().r#move(sum, y + offset);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "op add v0 @thisx offset\n\
+                 op add v1 y offset\n\
+                 set v2 v1\n\
+                 ucontrol move v1 v2 0 0 0\n\
+                 set v3 v0\n\
+                 ucontrol move v0 v3 0 0 0\n\
+                 op add y y 1\n\
+                 op add v4 y offset\n\
+                 ucontrol move v1 v4 0 0 0\n"
+            );
+        });
+    }
+}
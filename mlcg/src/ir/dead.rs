@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use super::{BasicBlock, Instruction, Pass, Program};
+use crate::String;
+
+/// remove the instructions whose results are never read, and the blocks which are never run
+///
+/// instructions with a side effect are always kept, see [`Instruction::has_side_effect`], and
+/// so are writes to `@` variables and to variables marked by [`crate::r#ref::Ref::keep_alive`]
+#[derive(Debug, Default)]
+pub struct DeadCodeElimination;
+
+impl DeadCodeElimination {
+    /// `true` if `instruction` only writes variables nothing else reads
+    fn is_dead(
+        program: &Program,
+        instruction: &Instruction,
+        reads: &HashMap<String, usize>,
+    ) -> bool {
+        if instruction.has_side_effect() {
+            return false;
+        }
+        instruction.outputs().into_iter().all(|output| {
+            let Some(name) = output.as_variable() else {
+                return true;
+            };
+            // an instruction reading its own result, like a counter, does not keep it alive
+            let own = instruction
+                .inputs()
+                .into_iter()
+                .filter(|input| input.as_variable() == Some(name))
+                .count();
+            !name.starts_with('@')
+                && !program.is_kept(name)
+                && reads.get(name).copied().unwrap_or_default() == own
+        })
+    }
+
+    fn remove_dead_stores(program: &mut Program) -> bool {
+        let mut reads: HashMap<String, usize> = HashMap::new();
+        for input in program.instructions().flat_map(Instruction::inputs) {
+            if let Some(name) = input.as_variable() {
+                *reads.entry(name.clone()).or_default() += 1;
+            }
+        }
+        let dead: Vec<Vec<bool>> = program
+            .blocks
+            .iter()
+            .map(|block| {
+                let instructions = block.instructions.iter();
+                instructions
+                    .map(|instruction| Self::is_dead(program, instruction, &reads))
+                    .collect()
+            })
+            .collect();
+        let mut changed = false;
+        for (block, dead) in program.blocks.iter_mut().zip(dead) {
+            changed |= dead.contains(&true);
+            let mut dead = dead.into_iter();
+            block.instructions.retain(|_| !dead.next().unwrap());
+        }
+        changed
+    }
+
+    /// remove the blocks not reachable from the first one, their labels are moved to the block
+    /// following them, as no jump targets them
+    fn remove_unreachable_blocks(program: &mut Program) -> bool {
+        let label_blocks = program.label_blocks();
+        let mut reachable = vec![false; program.blocks.len()];
+        let mut pending = vec![0];
+        while let Some(index) = pending.pop() {
+            if std::mem::replace(&mut reachable[index], true) {
+                continue;
+            }
+            pending.extend(program.successors(index, &label_blocks));
+        }
+
+        let mut changed = false;
+        let mut labels = vec![];
+        let mut blocks = vec![];
+        for (block, reachable) in std::mem::take(&mut program.blocks)
+            .into_iter()
+            .zip(reachable)
+        {
+            labels.extend(block.labels);
+            if block.instructions.is_empty() {
+                continue;
+            }
+            if !reachable {
+                changed = true;
+                continue;
+            }
+            blocks.push(BasicBlock {
+                labels: std::mem::take(&mut labels),
+                instructions: block.instructions,
+            });
+        }
+        // labels after the last instruction
+        if !labels.is_empty() || blocks.is_empty() {
+            blocks.push(BasicBlock {
+                labels,
+                instructions: vec![],
+            });
+        }
+        program.blocks = blocks;
+        changed
+    }
+}
+
+impl Pass for DeadCodeElimination {
+    fn name(&self) -> &'static str {
+        "dead-code-elimination"
+    }
+
+    fn run(&mut self, program: &mut Program) -> bool {
+        let stores = Self::remove_dead_stores(program);
+        let blocks = Self::remove_unreachable_blocks(program);
+        stores || blocks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::{DeadCodeElimination, PassManager},
+        processor::Processor,
+        types::number::Number,
+    };
+
+    #[test]
+    fn dead_code() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new().with(DeadCodeElimination));
+            let a = core.from_mdt::<Number>("a");
+            let b = core.from_mdt::<Number>("b");
+
+            // only read by the next op, which is dead too
+            let _ = (a + 1) * 2;
+            // read by other processors
+            let _ = (a - 1).save_as("shared").keep_alive();
+            // a counter which is never read elsewhere
+            let mut count = core.new_unnamed::<Number>().set_to(0);
+            count += 1;
+            let label = core.new_label();
+            core.jump(label, crate::flow::Condition::Always, 0, 0);
+            core.unit().r#move(a, b);
+            core.place_label(label);
+            core.unit().r#move(b, a);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "op sub v2 a 1\n\
+                 set shared v2\n\
+                 jump 3 always 0 0\n\
+                 ucontrol move b a 0 0 0\n"
+            );
+        });
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use super::{Instruction, Operand, Pass, Program};
+use crate::String;
+
+/// compute `op` like the game does, `None` for ops which are not worth or not safe to compute
+pub(crate) fn evaluate(op: &str, a: f64, b: f64) -> Option<f64> {
+    let bool = |b: bool| if b { 1.0 } else { 0.0 };
+    let long = |f: f64| f as i64;
+    let value = match op {
+        "add" => a + b,
+        "sub" => a - b,
+        "mul" => a * b,
+        "div" => a / b,
+        "idiv" => (a / b).floor(),
+        "mod" => a % b,
+        "emod" => ((a % b) + b) % b,
+        "pow" => a.powf(b),
+        "equal" => bool((a - b).abs() < 0.000001),
+        "notEqual" => bool((a - b).abs() >= 0.000001),
+        "land" => bool(a != 0.0 && b != 0.0),
+        "lessThan" => bool(a < b),
+        "lessThanEq" => bool(a <= b),
+        "greaterThan" => bool(a > b),
+        "greaterThanEq" => bool(a >= b),
+        "strictEqual" => bool(a == b),
+        "shl" => long(a).wrapping_shl(long(b) as u32) as f64,
+        "shr" => long(a).wrapping_shr(long(b) as u32) as f64,
+        "or" => (long(a) | long(b)) as f64,
+        "and" => (long(a) & long(b)) as f64,
+        "xor" => (long(a) ^ long(b)) as f64,
+        "not" => !long(a) as f64,
+        "max" => a.max(b),
+        "min" => a.min(b),
+        "abs" => a.abs(),
+        "log" => a.ln(),
+        "log10" => a.log10(),
+        "floor" => a.floor(),
+        "ceil" => a.ceil(),
+        "sqrt" => a.sqrt(),
+        "sin" => a.to_radians().sin(),
+        "cos" => a.to_radians().cos(),
+        "tan" => a.to_radians().tan(),
+        "asin" => a.asin().to_degrees(),
+        "acos" => a.acos().to_degrees(),
+        "atan" => a.atan().to_degrees(),
+        // `rand` and `noise` are not deterministic, `angle`, `angleDiff` and `len` are computed
+        // in single precision by the game
+        _ => return None,
+    };
+    value.is_finite().then_some(value)
+}
+
+/// `op`s which ignore their second operand
+fn is_unary(op: &str) -> bool {
+    matches!(
+        op,
+        "not"
+            | "abs"
+            | "log"
+            | "log10"
+            | "floor"
+            | "ceil"
+            | "sqrt"
+            | "sin"
+            | "cos"
+            | "tan"
+            | "asin"
+            | "acos"
+            | "atan"
+    )
+}
+
+/// variables whose every write is a `set` of the same numeric literal, and whose every read
+/// comes after one of these writes on every path from the start of the program
+///
+/// variables keep their value when the program restarts, and are `null` before their first
+/// write, so a read which may run before the write, like the guard of a run-once block, is not
+/// constant
+fn constant_variables(program: &Program) -> HashMap<String, Operand> {
+    struct Writes {
+        value: Option<Operand>,
+        /// the block and the index of each write
+        at: Vec<(usize, usize)>,
+    }
+
+    let mut writes: HashMap<String, Writes> = HashMap::new();
+    for (block, instructions) in program.blocks.iter().enumerate() {
+        for (index, instruction) in instructions.instructions.iter().enumerate() {
+            let (outputs, value) = match instruction.as_set() {
+                Some((result, value)) if value.as_number().is_some() => (vec![result], Some(value)),
+                _ => (instruction.outputs(), None),
+            };
+            for output in outputs {
+                let Some(variable) = output.as_variable() else {
+                    continue;
+                };
+                let value = value.filter(|_| !variable.starts_with('@')).cloned();
+                let writes = writes.entry(variable.clone()).or_insert_with(|| Writes {
+                    value: value.clone(),
+                    at: vec![],
+                });
+                if writes.value != value {
+                    writes.value = None;
+                }
+                writes.at.push((block, index));
+            }
+        }
+    }
+
+    let dominators = program.dominators();
+    let written_before = |writes: &Writes, block: usize, index: usize| {
+        writes
+            .at
+            .iter()
+            .any(|&(write_block, write_index)| match write_block == block {
+                true => write_index < index,
+                false => dominators[block][write_block],
+            })
+    };
+    for (block, instructions) in program.blocks.iter().enumerate() {
+        for (index, instruction) in instructions.instructions.iter().enumerate() {
+            for input in instruction.inputs() {
+                let Some(writes) = input.as_variable().and_then(|name| writes.get_mut(name)) else {
+                    continue;
+                };
+                if writes.value.is_some() && !written_before(writes, block, index) {
+                    writes.value = None;
+                }
+            }
+        }
+    }
+    writes
+        .into_iter()
+        .filter_map(|(variable, writes)| Some((variable, writes.value?)))
+        .collect()
+}
+
+/// replace reads of variables which always hold the same number with that number, and `op`s
+/// whose operands are numbers with a `set` of their result
+///
+/// `set`s of temporaries which are no longer read are removed
+#[derive(Debug, Default)]
+pub struct ConstantFolding;
+
+impl Pass for ConstantFolding {
+    fn name(&self) -> &'static str {
+        "constant-folding"
+    }
+
+    fn run(&mut self, program: &mut Program) -> bool {
+        let constants = constant_variables(program);
+        let mut changed = false;
+
+        for instruction in program.instructions_mut() {
+            for input in instruction.inputs_mut() {
+                let value = input.as_variable().and_then(|name| constants.get(name));
+                if let Some(value) = value {
+                    *input = value.clone();
+                    changed = true;
+                }
+            }
+
+            let Some((op, result, lhs, rhs)) = instruction.as_op() else {
+                continue;
+            };
+            let rhs = match is_unary(op) {
+                true => Some(0.0),
+                false => rhs.as_number(),
+            };
+            let value = lhs
+                .as_number()
+                .zip(rhs)
+                .and_then(|(lhs, rhs)| evaluate(op, lhs, rhs));
+            if let Some(value) = value {
+                *instruction = Instruction::set(result.clone(), Operand::from_number(value));
+                changed = true;
+            }
+        }
+
+        let temporaries = &program.temporaries;
+        for block in &mut program.blocks {
+            let before = block.instructions.len();
+            block
+                .instructions
+                .retain(|instruction| match instruction.as_set() {
+                    Some((Operand::Variable(result), _)) => {
+                        !(constants.contains_key(result) && temporaries.contains(result))
+                    }
+                    _ => true,
+                });
+            changed |= block.instructions.len() != before;
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::{ConstantFolding, PassManager},
+        processor::Processor,
+        types::number::Number,
+    };
+
+    #[test]
+    fn fold() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new().with(ConstantFolding));
+            let a = core.from_mdt::<Number>("a");
+
+            let width = core.new_unnamed::<Number>().set_to(50);
+            let half = width / 2;
+            let offset = (half + 1).sqrt().save_as("offset");
+            core.unit().r#move(a + half, offset);
+            let mut counter = core.new_unnamed::<Number>().set_to(0);
+            counter += half;
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "set offset 5.0990195135927845\n\
+                 op add v4 a 25\n\
+                 ucontrol move v4 5.0990195135927845 0 0 0\n\
+                 set v5 0\n\
+                 op add v5 v5 25\n"
+            );
+        });
+    }
+
+    #[test]
+    fn run_once() {
+        Processor::scope(|core| {
+            let init = core.new_uninit::<Number>("init");
+            let _ = core.if_(init.eq(0), |core| {
+                core.unit().r#move(1, 1);
+                init.set_to(1);
+            });
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "jump 0 notEqual init 0\n\
+                 ucontrol move 1 1 0 0 0\n\
+                 set init 1\n"
+            );
+        });
+
+        Processor::scope(|core| {
+            let n = core.new_uninit::<Number>("n");
+            let m = n + 1;
+            core.unit().r#move(m, m);
+            n.set_to(5);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "op add v0 n 1\n\
+                 ucontrol move v0 v0 0 0 0\n\
+                 set n 5\n"
+            );
+        });
+    }
+}
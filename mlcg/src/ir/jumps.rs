@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+
+use super::{fold::evaluate, temporaries::count_accesses, Instruction, Operand, Pass, Program};
+use crate::flow::Condition;
+
+/// simplify jumps
+///
+/// - a jump to an unconditional jump goes to the target of the latter instead
+/// - a jump to the next instruction is removed
+/// - a conditional jump over a single unconditional jump is negated and takes its target, so
+///   `jump a lessThan x y`, `jump b always`, `a:` becomes `jump b greaterThanEq x y`
+/// - a comparison `op` whose result is only read by the following jump is fused into it, so
+///   `op lessThan t x y`, `jump a notEqual t 0` becomes `jump a lessThan x y`
+/// - a jump comparing two numbers is removed if never taken, and made `always` otherwise
+#[derive(Debug, Default)]
+pub struct Peephole;
+
+impl Peephole {
+    fn fold_constant_jumps(program: &mut Program) -> bool {
+        let mut changed = false;
+        for block in &mut program.blocks {
+            block.instructions.retain_mut(|instruction| {
+                let Instruction::Jump {
+                    condition,
+                    lhs,
+                    rhs,
+                    ..
+                } = instruction
+                else {
+                    return true;
+                };
+                let (Some(a), Some(b)) = (lhs.as_number(), rhs.as_number()) else {
+                    return true;
+                };
+                if *condition == Condition::Always {
+                    return true;
+                }
+                let Some(taken) = evaluate(condition.name(), a, b) else {
+                    return true;
+                };
+                changed = true;
+                if taken == 0.0 {
+                    return false;
+                }
+                *condition = Condition::Always;
+                *lhs = Operand::from_number(0.0);
+                *rhs = Operand::from_number(0.0);
+                true
+            });
+        }
+        changed
+    }
+
+    fn thread_jumps(program: &mut Program) -> bool {
+        let label_blocks = program.label_blocks();
+        let blocks = &program.blocks;
+        let mut threaded = vec![];
+        for (block, instruction) in blocks.iter().enumerate() {
+            let Some(Instruction::Jump { target, .. }) = instruction.instructions.last() else {
+                continue;
+            };
+            let mut visited = HashSet::new();
+            let mut final_target = *target;
+            while visited.insert(final_target) {
+                match blocks[label_blocks[&final_target]].instructions.first() {
+                    Some(Instruction::Jump {
+                        target,
+                        condition: Condition::Always,
+                        ..
+                    }) => final_target = *target,
+                    _ => break,
+                }
+            }
+            if final_target != *target {
+                threaded.push((block, final_target));
+            }
+        }
+        for (block, final_target) in &threaded {
+            if let Some(Instruction::Jump { target, .. }) =
+                program.blocks[*block].instructions.last_mut()
+            {
+                *target = *final_target;
+            }
+        }
+        !threaded.is_empty()
+    }
+
+    fn remove_jumps_to_next(program: &mut Program) -> bool {
+        let addresses = program.addresses();
+        let len = program.len();
+        let mut position = 0;
+        let mut changed = false;
+        for block in &mut program.blocks {
+            block.instructions.retain(|instruction| {
+                position += 1;
+                let to_next = match instruction {
+                    Instruction::Jump { target, .. } => addresses[target] == position % len,
+                    _ => false,
+                };
+                changed |= to_next;
+                !to_next
+            });
+        }
+        changed
+    }
+
+    fn invert_conditions(program: &mut Program) -> bool {
+        let addresses = program.addresses();
+        let mut skipped_blocks = vec![];
+        // addresses of the blocks as they were before any change
+        let mut position = 0;
+        let lengths: Vec<_> = program
+            .blocks
+            .iter()
+            .map(|block| block.instructions.len())
+            .collect();
+        for (index, length) in lengths.into_iter().enumerate() {
+            position += length;
+            let [block, skipped, ..] = &mut program.blocks[index..] else {
+                break;
+            };
+            let Some(Instruction::Jump {
+                target, condition, ..
+            }) = block.instructions.last_mut()
+            else {
+                continue;
+            };
+            let Some(negated) = condition.negate() else {
+                continue;
+            };
+            let [Instruction::Jump {
+                target: skipped_target,
+                condition: Condition::Always,
+                ..
+            }] = skipped.instructions[..]
+            else {
+                continue;
+            };
+            if !skipped.labels.is_empty() || addresses[target] != position + 1 {
+                continue;
+            }
+            *target = skipped_target;
+            *condition = negated;
+            skipped.instructions.clear();
+            skipped_blocks.push(index + 1);
+        }
+        for index in skipped_blocks.iter().rev() {
+            program.blocks.remove(*index);
+        }
+        !skipped_blocks.is_empty()
+    }
+
+    /// `lhs` as a jump operand, and whether the condition holds when `lhs` is `false`
+    fn comparison(condition: Condition, lhs: &Operand, rhs: &Operand) -> Option<bool> {
+        let rhs = rhs.as_number()?;
+        lhs.as_variable()?;
+        match (condition, rhs == 0.0, rhs == 1.0) {
+            (Condition::Equal, true, _) | (Condition::NotEqual, _, true) => Some(true),
+            (Condition::NotEqual, true, _) | (Condition::Equal, _, true) => Some(false),
+            _ => None,
+        }
+    }
+
+    fn fuse_comparisons(program: &mut Program) -> bool {
+        let (reads, _) = count_accesses(program);
+        let mut changed = false;
+        for block in &mut program.blocks {
+            let [.., op, jump] = &block.instructions[..] else {
+                continue;
+            };
+            let Some((name, result, lhs, rhs)) = op.as_op() else {
+                continue;
+            };
+            let Some(comparison) = Condition::from_name(name) else {
+                continue;
+            };
+            let Instruction::Jump {
+                target,
+                condition,
+                lhs: a,
+                rhs: b,
+            } = jump
+            else {
+                continue;
+            };
+            let (tested, when_false) = match Self::comparison(*condition, a, b) {
+                Some(when_false) => (a, when_false),
+                None => match Self::comparison(*condition, b, a) {
+                    Some(when_false) => (b, when_false),
+                    None => continue,
+                },
+            };
+            let Some(name) = result.as_variable() else {
+                continue;
+            };
+            if tested != result
+                || reads.get(name) != Some(&1)
+                || program.kept.contains(name)
+                || name.starts_with('@')
+            {
+                continue;
+            }
+            let condition = match when_false {
+                true => comparison.negate(),
+                false => Some(comparison),
+            };
+            let Some(condition) = condition else {
+                continue;
+            };
+            let fused = Instruction::Jump {
+                target: *target,
+                condition,
+                lhs: lhs.clone(),
+                rhs: rhs.clone(),
+            };
+            block.instructions.pop();
+            *block.instructions.last_mut().unwrap() = fused;
+            changed = true;
+        }
+        changed
+    }
+}
+
+impl Pass for Peephole {
+    fn name(&self) -> &'static str {
+        "peephole"
+    }
+
+    fn run(&mut self, program: &mut Program) -> bool {
+        let mut changed = Self::fuse_comparisons(program);
+        changed |= Self::fold_constant_jumps(program);
+        changed |= Self::thread_jumps(program);
+        changed |= Self::invert_conditions(program);
+        changed |= Self::remove_jumps_to_next(program);
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        flow::Condition,
+        ir::{PassManager, Peephole},
+        processor::Processor,
+        types::number::Number,
+    };
+
+    #[test]
+    fn jumps() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new().with(Peephole));
+            let a = core.from_mdt::<Number>("a");
+
+            // fused into the jump
+            let start = core.label();
+            let below = a.lt(10);
+            core.jump_if(start, below);
+            // `jump` to the next instruction
+            let next = core.new_label();
+            core.jump(next, Condition::Always, 0, 0);
+            core.place_label(next);
+            // conditional jump over an unconditional one
+            let over = core.new_label();
+            let far = core.new_label();
+            core.jump(over, Condition::Equal, a, 1);
+            core.jump(far, Condition::Always, 0, 0);
+            core.place_label(over);
+            core.unit().r#move(a, a);
+            // chained jumps
+            let chain = core.new_label();
+            core.jump(chain, Condition::NotEqual, a, 2);
+            core.unit().r#move(0, 0);
+            core.place_label(chain);
+            core.jump(far, Condition::Always, 0, 0);
+            core.place_label(far);
+            core.unit().r#move(2, 2);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "jump 0 lessThan a 10\n\
+                 jump 5 notEqual a 1\n\
+                 ucontrol move a a 0 0 0\n\
+                 jump 5 notEqual a 2\n\
+                 ucontrol move 0 0 0 0 0\n\
+                 ucontrol move 2 2 0 0 0\n"
+            );
+        });
+    }
+
+    #[test]
+    fn constant_jumps() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new().with(Peephole));
+            let unit = core.unit();
+
+            let start = core.label();
+            unit.flag(1);
+            // never taken
+            core.jump(start, Condition::Equal, 1, false);
+            unit.flag(2);
+            // always taken
+            core.jump(start, Condition::LessThan, 1, 2);
+            unit.flag(3);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "ucontrol flag 1 0 0 0 0\n\
+                 ucontrol flag 2 0 0 0 0\n\
+                 jump 0 always 0 0\n\
+                 ucontrol flag 3 0 0 0 0\n"
+            );
+        });
+    }
+}
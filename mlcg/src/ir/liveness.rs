@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+
+use super::Program;
+use crate::String;
+
+/// the variables whose value may still be read, at the boundaries of every block of a
+/// [`Program`]
+///
+/// the program restarts when it ends, so variables read before being written in the first block
+/// are live at the end of the program as well
+#[derive(Debug, Clone)]
+pub struct Liveness {
+    pub live_in: Vec<HashSet<String>>,
+    pub live_out: Vec<HashSet<String>>,
+}
+
+impl Liveness {
+    pub fn new(program: &Program) -> Self {
+        let label_blocks = program.label_blocks();
+        let successors: Vec<_> = (0..program.blocks.len())
+            .map(|index| program.successors(index, &label_blocks))
+            .collect();
+        let (uses, defs): (Vec<_>, Vec<_>) = (0..program.blocks.len())
+            .map(|index| Self::uses_and_defs(program, index))
+            .unzip();
+
+        let mut live_in = vec![HashSet::new(); program.blocks.len()];
+        let mut live_out = vec![HashSet::new(); program.blocks.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for index in (0..program.blocks.len()).rev() {
+                let out: HashSet<String> = successors[index]
+                    .iter()
+                    .flat_map(|successor| live_in[*successor].iter().cloned())
+                    .collect();
+                let mut r#in: HashSet<String> = out.difference(&defs[index]).cloned().collect();
+                r#in.extend(uses[index].iter().cloned());
+                if r#in != live_in[index] || out != live_out[index] {
+                    live_in[index] = r#in;
+                    live_out[index] = out;
+                    changed = true;
+                }
+            }
+        }
+        Self { live_in, live_out }
+    }
+
+    /// the variables read before being written in a block, and the ones it writes
+    fn uses_and_defs(program: &Program, index: usize) -> (HashSet<String>, HashSet<String>) {
+        let mut uses = HashSet::new();
+        let mut defs = HashSet::new();
+        for instruction in &program.blocks[index].instructions {
+            for input in instruction.inputs() {
+                if let Some(name) = input.as_variable().filter(|name| !defs.contains(*name)) {
+                    uses.insert(name.clone());
+                }
+            }
+            for output in instruction.outputs() {
+                if let Some(name) = output.as_variable() {
+                    defs.insert(name.clone());
+                }
+            }
+        }
+        (uses, defs)
+    }
+
+    /// the variables live after each instruction of the block at `index`
+    pub fn live_after(&self, program: &Program, index: usize) -> Vec<HashSet<String>> {
+        let instructions = &program.blocks[index].instructions;
+        let mut live = self.live_out[index].clone();
+        let mut after = vec![HashSet::new(); instructions.len()];
+        for (position, instruction) in instructions.iter().enumerate().rev() {
+            after[position] = live.clone();
+            for output in instruction.outputs() {
+                if let Some(name) = output.as_variable() {
+                    live.remove(name);
+                }
+            }
+            for input in instruction.inputs() {
+                if let Some(name) = input.as_variable() {
+                    live.insert(name.clone());
+                }
+            }
+        }
+        after
+    }
+}
@@ -0,0 +1,726 @@
+//! the representation of a program between the builder API and mlog text, which the [`Pass`]es
+//! of a [`PassManager`] analyse and rewrite
+//!
+//! instructions read [`Operand`]s and write variables, jumps target [`LabelId`]s, and instructions
+//! are grouped in [`BasicBlock`]s
+
+mod copies;
+mod cse;
+mod dead;
+mod fold;
+mod jumps;
+mod liveness;
+mod pass;
+mod registers;
+mod split;
+mod temporaries;
+
+pub use copies::CopyPropagation;
+pub use cse::CommonSubexpressionElimination;
+pub use dead::DeadCodeElimination;
+pub use fold::ConstantFolding;
+pub use jumps::Peephole;
+pub use liveness::Liveness;
+pub use pass::{Pass, PassManager};
+pub use registers::RegisterAllocation;
+pub(crate) use split::split;
+pub use temporaries::TemporaryElimination;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    command::{self, Command},
+    eval::Eval,
+    flow::Condition,
+    processor::{LabelId, RawProcessor, Statement},
+    String,
+};
+
+/// an argument of an instruction
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Operand {
+    /// a name, `@` variables of the processor included
+    Variable(String),
+    /// a number, a string, `null`, `true`, `false`, or a content like `@copper`
+    Constant(String),
+}
+
+impl Operand {
+    /// processor variables, every other `@` name is a content
+    const PROCESSOR_VARIABLES: &'static [&'static str] = &[
+        "@unit",
+        "@counter",
+        "@this",
+        "@thisx",
+        "@thisy",
+        "@ipt",
+        "@links",
+        "@time",
+        "@tick",
+        "@second",
+        "@minute",
+        "@waveNumber",
+        "@waveTime",
+        "@mapw",
+        "@maph",
+        "@server",
+        "@client",
+    ];
+
+    /// classify an argument the way the game parses it
+    pub fn new(arg: String) -> Self {
+        let is_constant = match arg.chars().next() {
+            Some('@') => !Self::PROCESSOR_VARIABLES.contains(&&*arg),
+            Some('"') => true,
+            _ => {
+                matches!(&*arg, "null" | "true" | "false")
+                    || Self::Constant(arg.clone()).as_number().is_some()
+            }
+        };
+        match is_constant {
+            true => Self::Constant(arg),
+            false => Self::Variable(arg),
+        }
+    }
+
+    pub fn from_number(value: f64) -> Self {
+        Self::Constant(String::new(value))
+    }
+
+    /// the value of a numeric literal, `true` and `false` included, `None` for a variable
+    pub fn as_number(&self) -> Option<f64> {
+        let Self::Constant(arg) = self else {
+            return None;
+        };
+        let value: f64 = match &**arg {
+            "true" => 1.0,
+            "false" => 0.0,
+            arg => {
+                let digits = arg.strip_prefix('-').unwrap_or(arg);
+                let digits = digits.strip_prefix('.').unwrap_or(digits);
+                if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+                    return None;
+                }
+                arg.parse().ok()?
+            }
+        };
+        value.is_finite().then_some(value)
+    }
+
+    pub fn as_variable(&self) -> Option<&String> {
+        match self {
+            Self::Variable(name) => Some(name),
+            Self::Constant(..) => None,
+        }
+    }
+
+    pub fn is_variable(&self, name: &str) -> bool {
+        self.as_variable()
+            .is_some_and(|variable| **variable == *name)
+    }
+
+    pub fn arg(&self) -> &String {
+        match self {
+            Self::Variable(arg) | Self::Constant(arg) => arg,
+        }
+    }
+}
+
+impl std::fmt::Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.arg().fmt(f)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Instruction {
+    /// any command but `jump`, `command` is only used for its shape: its arguments are
+    /// replaced by `inputs` and `outputs` when rendered
+    Command {
+        command: Command,
+        inputs: Vec<Operand>,
+        outputs: Vec<Operand>,
+    },
+    Jump {
+        target: LabelId,
+        condition: Condition,
+        lhs: Operand,
+        rhs: Operand,
+    },
+    /// `set` of the address of `label`
+    Address { result: Operand, label: LabelId },
+}
+
+impl Instruction {
+    pub fn new(command: impl Into<Command>) -> Self {
+        let command = command.into();
+        let inputs = command.inputs().into_iter().cloned().map(Operand::new);
+        let outputs = command.outputs().into_iter().cloned().map(Operand::new);
+        Self::Command {
+            inputs: inputs.collect(),
+            outputs: outputs.collect(),
+            command,
+        }
+    }
+
+    pub fn set(result: Operand, value: Operand) -> Self {
+        Self::Command {
+            command: command::set::Set {
+                result: result.arg().clone(),
+                value: value.arg().clone(),
+            }
+            .into(),
+            inputs: vec![value],
+            outputs: vec![result],
+        }
+    }
+
+    pub fn inputs(&self) -> Vec<&Operand> {
+        match self {
+            Self::Command { inputs, .. } => inputs.iter().collect(),
+            Self::Jump { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::Address { .. } => vec![],
+        }
+    }
+
+    pub fn inputs_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Self::Command { inputs, .. } => inputs.iter_mut().collect(),
+            Self::Jump { lhs, rhs, .. } => vec![lhs, rhs],
+            Self::Address { .. } => vec![],
+        }
+    }
+
+    pub fn outputs(&self) -> Vec<&Operand> {
+        match self {
+            Self::Command { outputs, .. } => outputs.iter().collect(),
+            Self::Jump { .. } => vec![],
+            Self::Address { result, .. } => vec![result],
+        }
+    }
+
+    pub fn outputs_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Self::Command { outputs, .. } => outputs.iter_mut().collect(),
+            Self::Jump { .. } => vec![],
+            Self::Address { result, .. } => vec![result],
+        }
+    }
+
+    /// the value of a `set`
+    pub fn as_set(&self) -> Option<(&Operand, &Operand)> {
+        match self {
+            Self::Command {
+                command: Command::Set(..),
+                inputs,
+                outputs,
+            } => Some((&outputs[0], &inputs[0])),
+            _ => None,
+        }
+    }
+
+    /// the operator, result and operands of an `op`
+    pub fn as_op(&self) -> Option<(&str, &Operand, &Operand, &Operand)> {
+        match self {
+            Self::Command {
+                command: Command::Op(op),
+                inputs,
+                outputs,
+            } => Some((&op.op, &outputs[0], &inputs[0], &inputs[1])),
+            _ => None,
+        }
+    }
+
+    /// writes `@counter`, so it is a jump to a computed address
+    pub fn writes_counter(&self) -> bool {
+        self.outputs()
+            .iter()
+            .any(|output| output.is_variable("@counter"))
+    }
+
+    /// `end`, a `jump` or a write to `@counter`, which ends its [`BasicBlock`]
+    pub fn is_terminator(&self) -> bool {
+        match self {
+            Self::Command {
+                command: Command::End(..),
+                ..
+            }
+            | Self::Jump { .. } => true,
+            _ => self.writes_counter(),
+        }
+    }
+
+    /// `true` unless the instruction only writes its outputs
+    pub fn has_side_effect(&self) -> bool {
+        match self {
+            Self::Command { command, .. } => {
+                !matches!(
+                    command,
+                    Command::Set(..)
+                        | Command::Op(..)
+                        | Command::Read(..)
+                        | Command::Getlink(..)
+                        | Command::Sensor(..)
+                        | Command::Radar(..)
+                        | Command::Uradar(..)
+                        | Command::Ulocate(..)
+                ) || self.writes_counter()
+            }
+            Self::Jump { .. } => true,
+            Self::Address { .. } => false,
+        }
+    }
+
+    fn render(&self, addresses: &HashMap<LabelId, usize>) -> Command {
+        match self {
+            Self::Command {
+                command,
+                inputs,
+                outputs,
+            } => {
+                let mut command = command.clone();
+                for (arg, operand) in command.inputs_mut().into_iter().zip(inputs) {
+                    *arg = operand.arg().clone();
+                }
+                for (arg, operand) in command.outputs_mut().into_iter().zip(outputs) {
+                    *arg = operand.arg().clone();
+                }
+                command
+            }
+            Self::Jump {
+                target,
+                condition,
+                lhs,
+                rhs,
+            } => command::jump::Jump {
+                target: String::new(addresses[target]),
+                cond: condition.eval(),
+                lhs: lhs.arg().clone(),
+                rhs: rhs.arg().clone(),
+            }
+            .into(),
+            Self::Address { result, label } => command::set::Set {
+                result: result.arg().clone(),
+                value: String::new(addresses[label]),
+            }
+            .into(),
+        }
+    }
+}
+
+/// instructions entered only at the start and left only at the end
+#[derive(Debug, Clone, Default)]
+pub struct BasicBlock {
+    /// the labels placed at the start of the block
+    pub labels: Vec<LabelId>,
+    pub instructions: Vec<Instruction>,
+}
+
+impl BasicBlock {
+    pub fn terminator(&self) -> Option<&Instruction> {
+        self.instructions
+            .last()
+            .filter(|instruction| instruction.is_terminator())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    /// blocks in the order they are emitted, the program starts at the first one
+    pub blocks: Vec<BasicBlock>,
+    /// variables allocated by the generator, which are not visible to the user
+    pub temporaries: HashSet<String>,
+    /// variables marked by [`crate::r#ref::Ref::keep_alive`], whose writes are never removed
+    pub kept: HashSet<String>,
+    /// number of labels allocated
+    pub labels: usize,
+}
+
+impl Program {
+    /// lower every block of `raw`
+    pub(crate) fn new(raw: &RawProcessor) -> Self {
+        let mut program = Self {
+            blocks: vec![BasicBlock::default()],
+            temporaries: raw.temporaries.clone(),
+            kept: raw.kept.clone(),
+            labels: raw.labels,
+        };
+        let lowered = raw.lower();
+        // an unplaced label is reported by the generator, placing it at the end keeps every jump
+        // valid until then
+        let unplaced = raw.unplaced_labels(&lowered);
+        for statement in lowered {
+            let instruction = match statement {
+                Statement::Label(label) => {
+                    program.place_label(label);
+                    continue;
+                }
+                Statement::Command(command) => Instruction::new(command),
+                Statement::Jump {
+                    target,
+                    condition,
+                    lhs,
+                    rhs,
+                } => Instruction::Jump {
+                    target,
+                    condition,
+                    lhs: Operand::new(lhs),
+                    rhs: Operand::new(rhs),
+                },
+                Statement::Address { result, label } => Instruction::Address {
+                    result: Operand::new(result),
+                    label,
+                },
+                Statement::If { .. } => unreachable!("`if` is lowered before"),
+            };
+            program.push(instruction);
+        }
+        for label in unplaced {
+            program.place_label(label);
+        }
+        program
+    }
+
+    /// a label which is not placed yet
+    pub fn new_label(&mut self) -> LabelId {
+        self.labels += 1;
+        LabelId(self.labels - 1)
+    }
+
+    /// place `label` before the next instruction pushed
+    pub fn place_label(&mut self, label: LabelId) {
+        if !self.blocks.last().unwrap().instructions.is_empty() {
+            self.blocks.push(BasicBlock::default());
+        }
+        self.blocks.last_mut().unwrap().labels.push(label);
+    }
+
+    /// add `instruction` at the end of the program
+    pub fn push(&mut self, instruction: Instruction) {
+        if self.blocks.last().unwrap().terminator().is_some() {
+            self.blocks.push(BasicBlock::default());
+        }
+        self.blocks
+            .last_mut()
+            .unwrap()
+            .instructions
+            .push(instruction);
+    }
+
+    pub fn is_temporary(&self, name: &String) -> bool {
+        self.temporaries.contains(name)
+    }
+
+    pub fn is_kept(&self, name: &String) -> bool {
+        self.kept.contains(name)
+    }
+
+    /// number of instructions
+    pub fn len(&self) -> usize {
+        self.blocks
+            .iter()
+            .map(|block| block.instructions.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn instructions(&self) -> impl Iterator<Item = &Instruction> {
+        self.blocks.iter().flat_map(|block| &block.instructions)
+    }
+
+    pub fn instructions_mut(&mut self) -> impl Iterator<Item = &mut Instruction> {
+        self.blocks
+            .iter_mut()
+            .flat_map(|block| &mut block.instructions)
+    }
+
+    /// the index of the block each label is placed in
+    pub fn label_blocks(&self) -> HashMap<LabelId, usize> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .flat_map(|(index, block)| block.labels.iter().map(move |label| (*label, index)))
+            .collect()
+    }
+
+    /// the blocks which may run after `index`
+    ///
+    /// running past the last block or `end` restarts the program. a write to `@counter` of a
+    /// variable only written by [`Instruction::Address`] goes to a block whose address is taken,
+    /// any other write to `@counter` may go anywhere
+    pub fn successors(&self, index: usize, label_blocks: &HashMap<LabelId, usize>) -> Vec<usize> {
+        let next = match index + 1 == self.blocks.len() {
+            true => 0,
+            false => index + 1,
+        };
+        match self.blocks[index].terminator() {
+            None => vec![next],
+            Some(Instruction::Jump {
+                target, condition, ..
+            }) => match condition {
+                Condition::Always => vec![label_blocks[target]],
+                _ => vec![label_blocks[target], next],
+            },
+            Some(instruction) if instruction.writes_counter() => {
+                let address = instruction
+                    .as_set()
+                    .and_then(|(_, value)| value.as_variable())
+                    .filter(|value| self.is_address(value));
+                match address {
+                    Some(..) => self
+                        .instructions()
+                        .filter_map(|instruction| match instruction {
+                            Instruction::Address { label, .. } => Some(label_blocks[label]),
+                            _ => None,
+                        })
+                        .collect(),
+                    None => (0..self.blocks.len()).collect(),
+                }
+            }
+            Some(..) => vec![0],
+        }
+    }
+
+    /// for each block, the blocks run before it on every path from the start of the program,
+    /// itself included
+    ///
+    /// blocks which are never run are dominated by every block
+    pub fn dominators(&self) -> Vec<Vec<bool>> {
+        let label_blocks = self.label_blocks();
+        let count = self.blocks.len();
+        let mut predecessors = vec![vec![]; count];
+        for index in 0..count {
+            for successor in self.successors(index, &label_blocks) {
+                predecessors[successor].push(index);
+            }
+        }
+        let mut dominators = vec![vec![true; count]; count];
+        dominators[0] = (0..count).map(|index| index == 0).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for index in 1..count {
+                let mut dominated = vec![true; count];
+                for predecessor in &predecessors[index] {
+                    for (block, dominates) in dominated.iter_mut().enumerate() {
+                        *dominates &= dominators[*predecessor][block];
+                    }
+                }
+                dominated[index] = true;
+                if dominated != dominators[index] {
+                    dominators[index] = dominated;
+                    changed = true;
+                }
+            }
+        }
+        dominators
+    }
+
+    /// `true` if every write to `name` is an [`Instruction::Address`]
+    fn is_address(&self, name: &String) -> bool {
+        self.instructions().all(|instruction| {
+            matches!(instruction, Instruction::Address { .. })
+                || instruction
+                    .outputs()
+                    .into_iter()
+                    .all(|output| output.as_variable() != Some(name))
+        })
+    }
+
+    /// the number of instructions on the longest path from the first block back to it, which
+    /// runs every block of a loop once
+    ///
+    /// the blocks which can reach each other are counted together, so the result does not depend
+    /// on the block a loop is entered by
+    pub fn longest_path(&self) -> usize {
+        /// tarjan's algorithm, a component is complete after every component it reaches, so their
+        /// lengths are known when its own is
+        struct Components {
+            successors: Vec<Vec<usize>>,
+            sizes: Vec<usize>,
+            visited: usize,
+            order: Vec<Option<usize>>,
+            low: Vec<usize>,
+            stack: Vec<usize>,
+            component: Vec<Option<usize>>,
+            lengths: Vec<usize>,
+        }
+
+        impl Components {
+            fn visit(&mut self, index: usize) {
+                self.order[index] = Some(self.visited);
+                self.low[index] = self.visited;
+                self.visited += 1;
+                self.stack.push(index);
+                for successor in self.successors[index].clone() {
+                    match self.order[successor] {
+                        None => {
+                            self.visit(successor);
+                            self.low[index] = self.low[index].min(self.low[successor]);
+                        }
+                        Some(order) if self.component[successor].is_none() => {
+                            self.low[index] = self.low[index].min(order);
+                        }
+                        Some(_) => {}
+                    }
+                }
+                if Some(self.low[index]) != self.order[index] {
+                    return;
+                }
+                let component = self.lengths.len();
+                let start = self
+                    .stack
+                    .iter()
+                    .rposition(|block| *block == index)
+                    .unwrap();
+                let members = self.stack.split_off(start);
+                for member in &members {
+                    self.component[*member] = Some(component);
+                }
+                let size: usize = members.iter().map(|member| self.sizes[*member]).sum();
+                let rest = members
+                    .iter()
+                    .flat_map(|member| &self.successors[*member])
+                    .filter_map(|successor| self.component[*successor])
+                    .filter(|successor| *successor != component)
+                    .map(|successor| self.lengths[successor])
+                    .max()
+                    .unwrap_or(0);
+                self.lengths.push(size + rest);
+            }
+        }
+
+        let label_blocks = self.label_blocks();
+        let count = self.blocks.len();
+        let mut components = Components {
+            successors: (0..count)
+                .map(|index| {
+                    let mut successors = self.successors(index, &label_blocks);
+                    successors.retain(|successor| *successor != 0);
+                    successors
+                })
+                .collect(),
+            sizes: self
+                .blocks
+                .iter()
+                .map(|block| block.instructions.len())
+                .collect(),
+            visited: 0,
+            order: vec![None; count],
+            low: vec![0; count],
+            stack: vec![],
+            component: vec![None; count],
+            lengths: vec![],
+        };
+        components.visit(0);
+        components.lengths[components.component[0].unwrap()]
+    }
+
+    /// the instruction index of every label, a label after the last instruction is placed at `0`
+    /// since running past the end restarts the program, and the game rejects jumps out of range
+    pub fn addresses(&self) -> HashMap<LabelId, usize> {
+        let mut addresses = HashMap::new();
+        let mut index = 0;
+        for block in &self.blocks {
+            for label in &block.labels {
+                addresses.insert(*label, index);
+            }
+            index += block.instructions.len();
+        }
+        for address in addresses.values_mut() {
+            if *address == index {
+                *address = 0;
+            }
+        }
+        addresses
+    }
+
+    pub fn render(&self) -> std::string::String {
+        use std::fmt::Write;
+        let addresses = self.addresses();
+        self.instructions()
+            .map(|instruction| instruction.render(&addresses))
+            .fold(std::string::String::new(), |mut buffer, command| {
+                buffer.write_fmt(format_args!("{command}\n")).ok();
+                buffer
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        processor::Processor,
+        types::{boolean::Bool, number::Number},
+    };
+
+    #[test]
+    fn operand() {
+        let operand = |arg: &'static str| Operand::new(String::Static(arg));
+        assert_eq!(operand("1.5").as_number(), Some(1.5));
+        assert_eq!(operand("-.5").as_number(), Some(-0.5));
+        assert_eq!(operand("true").as_number(), Some(1.0));
+        assert!(operand("\"text\"").as_variable().is_none());
+        assert!(operand("@copper").as_variable().is_none());
+        assert!(operand("@unit").is_variable("@unit"));
+        assert!(operand("e1").is_variable("e1"));
+    }
+
+    #[test]
+    fn blocks() {
+        Processor::scope(|core| {
+            let a = core.from_mdt::<Number>("a");
+            core.while_(
+                |_| a.lt(10),
+                |_, _| {
+                    let _ = a.set_to(a + 1);
+                },
+            );
+
+            let raw = core.borrow();
+            let program = Program::new(&raw);
+            let labels = program.label_blocks();
+            // the loop starts the program, and its end label is an empty last block
+            assert_eq!(program.blocks.len(), 3);
+            assert_eq!(program.successors(0, &labels), [2, 1]);
+            assert_eq!(program.successors(1, &labels), [0]);
+            assert_eq!(program.successors(2, &labels), [0]);
+            let dominators = program.dominators();
+            assert_eq!(dominators[1], [true, true, false]);
+            assert_eq!(dominators[2], [true, false, true]);
+        });
+    }
+
+    #[test]
+    fn longest_path() {
+        Processor::scope(|core| {
+            let unit = core.unit();
+            let a = core.from_mdt::<Bool>("a");
+            let (x, b) = (core.new_label(), core.new_label());
+            unit.flag(0);
+            core.jump_if(b, a);
+            unit.flag(1);
+            core.place_label(x);
+            unit.flag(2);
+            core.jump_if(b, a);
+            core.place_label(b);
+            unit.flag(3);
+            core.jump_if(x, a);
+            for i in 4..7 {
+                unit.flag(i as f64);
+            }
+
+            let raw = core.borrow();
+            let program = Program::new(&raw);
+            let labels = program.label_blocks();
+            assert_eq!(program.successors(0, &labels), [3, 1]);
+            // the loop between `x` and `b` is entered by `b` first, but the path entering it by
+            // `x` runs every block
+            assert_eq!(program.longest_path(), 10);
+        });
+    }
+}
@@ -0,0 +1,188 @@
+use super::{
+    CommonSubexpressionElimination, ConstantFolding, CopyPropagation, DeadCodeElimination,
+    Peephole, Program, RegisterAllocation, TemporaryElimination,
+};
+
+/// an analysis or a rewrite of a [`Program`]
+pub trait Pass {
+    /// a short name, used when listing the passes of a [`PassManager`]
+    fn name(&self) -> &'static str;
+
+    /// run the pass once, returns `true` if `program` was changed
+    fn run(&mut self, program: &mut Program) -> bool;
+}
+
+/// the passes run by [`crate::processor::Processor::generate`]
+///
+/// passes run in the order they were added, and the whole list is run again as long as a pass
+/// changes the program, at most [`PassManager::MAX_ROUNDS`] times. the passes added with
+/// [`PassManager::finally`] then run once each, in the order they were added
+pub struct PassManager {
+    passes: Vec<Box<dyn Pass>>,
+    finally: Vec<Box<dyn Pass>>,
+}
+
+impl PassManager {
+    pub const MAX_ROUNDS: usize = 32;
+
+    /// a manager without any pass, the program is rendered as built
+    pub fn new() -> Self {
+        Self {
+            passes: vec![],
+            finally: vec![],
+        }
+    }
+
+    pub fn add(&mut self, pass: impl Pass + 'static) -> &mut Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    pub fn with(mut self, pass: impl Pass + 'static) -> Self {
+        self.add(pass);
+        self
+    }
+
+    /// add a pass run once after the others stop changing the program, like a pass whose
+    /// output the others can not work on
+    pub fn finally(mut self, pass: impl Pass + 'static) -> Self {
+        self.finally.push(Box::new(pass));
+        self
+    }
+
+    /// the names of the passes, in the order they run
+    pub fn names(&self) -> Vec<&'static str> {
+        self.passes
+            .iter()
+            .chain(&self.finally)
+            .map(|pass| pass.name())
+            .collect()
+    }
+
+    /// run every pass until none of them changes `program`
+    pub fn run(&mut self, program: &mut Program) {
+        for _ in 0..Self::MAX_ROUNDS {
+            let mut changed = false;
+            for pass in &mut self.passes {
+                changed |= pass.run(program);
+            }
+            if !changed {
+                break;
+            }
+        }
+        for pass in &mut self.finally {
+            pass.run(program);
+        }
+    }
+}
+
+impl Default for PassManager {
+    /// the passes applied unless configured otherwise
+    fn default() -> Self {
+        Self::new()
+            .with(ConstantFolding)
+            .with(TemporaryElimination)
+            .with(CopyPropagation)
+            .with(CommonSubexpressionElimination)
+            .with(DeadCodeElimination)
+            .with(Peephole)
+            .finally(RegisterAllocation)
+    }
+}
+
+impl std::fmt::Debug for PassManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ir::Operand, processor::Processor, types::number::Number, String};
+    use std::{cell::Cell, rc::Rc};
+
+    /// rename `a` to `b`
+    struct Rename;
+
+    impl Pass for Rename {
+        fn name(&self) -> &'static str {
+            "rename"
+        }
+
+        fn run(&mut self, program: &mut Program) -> bool {
+            let mut changed = false;
+            for instruction in program.instructions_mut() {
+                for input in instruction.inputs_mut() {
+                    if input.is_variable("a") {
+                        *input = Operand::Variable(String::Static("b"));
+                        changed = true;
+                    }
+                }
+            }
+            changed
+        }
+    }
+
+    #[test]
+    fn passes() {
+        assert_eq!(
+            PassManager::default().names(),
+            [
+                "constant-folding",
+                "temporary-elimination",
+                "copy-propagation",
+                "common-subexpression-elimination",
+                "dead-code-elimination",
+                "peephole",
+                "register-allocation"
+            ]
+        );
+
+        Processor::scope(|core| {
+            let a = core.from_mdt::<Number>("a");
+            let _ = (a + 1) * 2;
+            core.set_passes(PassManager::new());
+            assert_eq!(core.generate().unwrap(), "op add v0 a 1\nop mul v1 v0 2\n");
+        });
+
+        Processor::scope(|core| {
+            let a = core.from_mdt::<Number>("a");
+            let _ = (a + 1) * a;
+            core.set_passes(PassManager::new().with(Rename));
+            assert_eq!(core.generate().unwrap(), "op add v0 b 1\nop mul v1 v0 b\n");
+        });
+    }
+
+    /// count the runs, always claiming to change the program
+    struct Count(Rc<Cell<usize>>);
+
+    impl Pass for Count {
+        fn name(&self) -> &'static str {
+            "count"
+        }
+
+        fn run(&mut self, _: &mut Program) -> bool {
+            self.0.set(self.0.get() + 1);
+            true
+        }
+    }
+
+    #[test]
+    fn finally() {
+        Processor::scope(|core| {
+            let a = core.from_mdt::<Number>("a");
+            let _ = (a + 1) * a;
+            let (rounds, finally) = (Rc::new(Cell::new(0)), Rc::new(Cell::new(0)));
+            core.set_passes(
+                PassManager::new()
+                    .with(Count(rounds.clone()))
+                    .finally(Count(finally.clone()))
+                    .finally(Rename),
+            );
+            assert_eq!(core.generate().unwrap(), "op add v0 b 1\nop mul v1 v0 b\n");
+            assert_eq!(rounds.get(), PassManager::MAX_ROUNDS);
+            assert_eq!(finally.get(), 1);
+        });
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{Liveness, Operand, Pass, Program};
+use crate::String;
+
+/// give temporaries whose values are never needed at the same time the same name
+///
+/// temporaries are coloured in the order they first appear, each one taking the first name which
+/// is not taken by a temporary live when it is written. a temporary copied by a `set` does not
+/// conflict with its copy. the names are `v0`, `v1`... skipping the ones used by other variables.
+///
+/// renaming temporaries defeats the passes which look at a temporary as a single value, so this
+/// pass should run last, with [`super::PassManager::finally`]
+#[derive(Debug, Default)]
+pub struct RegisterAllocation;
+
+impl RegisterAllocation {
+    fn is_allocatable(program: &Program, name: &String) -> bool {
+        program.is_temporary(name) && !program.is_kept(name)
+    }
+
+    /// the temporaries in the order they first appear
+    fn temporaries(program: &Program) -> Vec<String> {
+        let mut seen = HashSet::new();
+        program
+            .instructions()
+            .flat_map(|instruction| {
+                instruction
+                    .inputs()
+                    .into_iter()
+                    .chain(instruction.outputs())
+            })
+            .filter_map(|operand| operand.as_variable())
+            .filter(|name| Self::is_allocatable(program, name) && seen.insert(*name))
+            .cloned()
+            .collect()
+    }
+
+    /// the temporaries which may not share a name with each temporary
+    fn interference(program: &Program) -> HashMap<String, HashSet<String>> {
+        let liveness = Liveness::new(program);
+        let mut interference: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut interfere = |a: &String, b: &String| {
+            if a != b {
+                interference.entry(a.clone()).or_default().insert(b.clone());
+                interference.entry(b.clone()).or_default().insert(a.clone());
+            }
+        };
+        for (index, block) in program.blocks.iter().enumerate() {
+            let after = liveness.live_after(program, index);
+            for (instruction, live) in block.instructions.iter().zip(after) {
+                let copied = instruction
+                    .as_set()
+                    .and_then(|(_, value)| value.as_variable());
+                let outputs: Vec<_> = instruction
+                    .outputs()
+                    .into_iter()
+                    .filter_map(|output| output.as_variable())
+                    .filter(|name| Self::is_allocatable(program, name))
+                    .collect();
+                for output in &outputs {
+                    let live = live.iter().filter(|name| Some(*name) != copied);
+                    for other in live.chain(outputs.iter().copied()) {
+                        if Self::is_allocatable(program, other) {
+                            interfere(output, other);
+                        }
+                    }
+                }
+            }
+        }
+        interference
+    }
+}
+
+impl Pass for RegisterAllocation {
+    fn name(&self) -> &'static str {
+        "register-allocation"
+    }
+
+    fn run(&mut self, program: &mut Program) -> bool {
+        let interference = Self::interference(program);
+        let mut colors: HashMap<String, usize> = HashMap::new();
+        for temporary in Self::temporaries(program) {
+            let taken: HashSet<usize> = interference
+                .get(&temporary)
+                .into_iter()
+                .flatten()
+                .filter_map(|other| colors.get(other).copied())
+                .collect();
+            let color = (0..).find(|color| !taken.contains(color)).unwrap();
+            colors.insert(temporary, color);
+        }
+
+        let reserved: HashSet<String> = program
+            .instructions()
+            .flat_map(|instruction| {
+                instruction
+                    .inputs()
+                    .into_iter()
+                    .chain(instruction.outputs())
+            })
+            .filter_map(|operand| operand.as_variable())
+            .filter(|name| !Self::is_allocatable(program, name))
+            .cloned()
+            .collect();
+        let registers: Vec<String> = (0..)
+            .map(|index| String::new(format!("v{index}")))
+            .filter(|name| !reserved.contains(name))
+            .take(colors.values().max().map_or(0, |max| max + 1))
+            .collect();
+
+        let mut changed = false;
+        let mut rename = |operand: &mut Operand| {
+            let Some(color) = operand.as_variable().and_then(|name| colors.get(name)) else {
+                return;
+            };
+            let register = &registers[*color];
+            if operand.arg() != register {
+                *operand = Operand::Variable(register.clone());
+                changed = true;
+            }
+        };
+        for instruction in program.instructions_mut() {
+            instruction.inputs_mut().into_iter().for_each(&mut rename);
+            instruction.outputs_mut().into_iter().for_each(&mut rename);
+        }
+        // a copy between temporaries given the same name
+        for block in &mut program.blocks {
+            let before = block.instructions.len();
+            block.instructions.retain(|instruction| {
+                let copy = instruction.as_set();
+                copy.is_none_or(|(result, value)| result != value)
+            });
+            changed |= block.instructions.len() != before;
+        }
+        program.temporaries.extend(registers);
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::{PassManager, RegisterAllocation},
+        processor::Processor,
+        types::number::Number,
+    };
+
+    #[test]
+    fn registers() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new().finally(RegisterAllocation));
+            let a = core.from_mdt::<Number>("a");
+            let b = core.from_mdt::<Number>("b");
+
+            let x = (a + 1) * 2;
+            core.unit().r#move(x, b);
+            let y = (a - 1) / 2;
+            core.unit().r#move(x, y);
+            let z = y.sqrt();
+            core.unit().r#move(z, z);
+            // a copy given the name of its source
+            let _ = z.save_as("");
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "op add v0 a 1\n\
+                 op mul v0 v0 2\n\
+                 ucontrol move v0 b 0 0 0\n\
+                 op sub v1 a 1\n\
+                 op div v1 v1 2\n\
+                 ucontrol move v0 v1 0 0 0\n\
+                 op sqrt v0 v1 0\n\
+                 ucontrol move v0 v0 0 0 0\n"
+            );
+        });
+    }
+}
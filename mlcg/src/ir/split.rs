@@ -0,0 +1,443 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use super::{BasicBlock, Instruction, Operand, PassManager, Peephole, Program};
+use crate::{
+    command::{self, Command},
+    flow::Condition,
+    limits::SplitError,
+    processor::LabelId,
+    String,
+};
+
+/// the variable holding the block to run, kept in the first slot of the memory
+const PC: String = String::Static("__pc");
+
+/// the parts of a program split across processors
+///
+/// every block of the program gets an id, its index, and each processor runs a range of
+/// consecutive blocks. a processor reads the id of the block to run from the first slot of the
+/// memory and restarts until it is one of its own. it then reads the shared variables from the
+/// memory, runs its blocks, and when control leaves them it writes the shared variables back and
+/// the id of the next block last. the memory starts with `0`, so the processor owning the first
+/// block runs first
+struct Splitter<'a> {
+    program: &'a Program,
+    memory: Operand,
+    label_blocks: HashMap<LabelId, usize>,
+    /// blocks jumping to each block
+    sources: Vec<Vec<usize>>,
+    /// blocks whose address is taken by [`Instruction::Address`]
+    addresses: HashSet<usize>,
+}
+
+impl<'a> Splitter<'a> {
+    fn new(program: &'a Program, memory: &String) -> Self {
+        let label_blocks = program.label_blocks();
+        let mut sources = vec![vec![]; program.blocks.len()];
+        let mut addresses = HashSet::new();
+        for (index, block) in program.blocks.iter().enumerate() {
+            for instruction in &block.instructions {
+                match instruction {
+                    Instruction::Jump { target, .. } => sources[label_blocks[target]].push(index),
+                    Instruction::Address { label, .. } => {
+                        addresses.insert(label_blocks[label]);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Self {
+            program,
+            memory: Operand::new(memory.clone()),
+            label_blocks,
+            sources,
+            addresses,
+        }
+    }
+
+    /// `true` if running past the end of the block runs the next one
+    fn falls_through(block: &BasicBlock) -> bool {
+        match block.terminator() {
+            None => true,
+            Some(Instruction::Jump { condition, .. }) => *condition != Condition::Always,
+            Some(..) => false,
+        }
+    }
+
+    /// the variables the blocks of `range` read or write, and the ones they write
+    fn accesses(&self, range: Range<usize>) -> (HashSet<&'a String>, HashSet<&'a String>) {
+        let mut mentioned = HashSet::new();
+        let mut written = HashSet::new();
+        for block in &self.program.blocks[range] {
+            for instruction in &block.instructions {
+                let inputs = instruction.inputs().into_iter().map(|input| (input, false));
+                let outputs = instruction
+                    .outputs()
+                    .into_iter()
+                    .map(|output| (output, true));
+                for (operand, write) in inputs.chain(outputs) {
+                    let Some(name) = operand.as_variable().filter(|name| !name.starts_with('@'))
+                    else {
+                        continue;
+                    };
+                    mentioned.insert(name);
+                    if write {
+                        written.insert(name);
+                    }
+                }
+            }
+        }
+        (mentioned, written)
+    }
+
+    /// the blocks of `range` which may be run by another processor: the first one and the one
+    /// starting the program, the ones jumped to from outside of `range` and the ones whose address
+    /// is taken
+    fn entries(&self, range: Range<usize>) -> Vec<usize> {
+        range
+            .clone()
+            .filter(|index| {
+                *index == range.start
+                    || *index == 0
+                    || self.addresses.contains(index)
+                    || self.sources[*index]
+                        .iter()
+                        .any(|source| !range.contains(source))
+            })
+            .collect()
+    }
+
+    /// the variables written by the program, in the order they first appear
+    fn variables(&self) -> Vec<&'a String> {
+        let (_, written) = self.accesses(0..self.program.blocks.len());
+        let mut variables = vec![];
+        for instruction in self.program.instructions() {
+            for operand in instruction
+                .inputs()
+                .into_iter()
+                .chain(instruction.outputs())
+            {
+                let Some(name) = operand.as_variable() else {
+                    continue;
+                };
+                if written.contains(name) && !variables.contains(&name) {
+                    variables.push(name);
+                }
+            }
+        }
+        variables
+    }
+
+    /// the variables used in one part and written in another
+    fn shared(&self, parts: &[Range<usize>]) -> Vec<&'a String> {
+        let accesses: Vec<_> = parts
+            .iter()
+            .map(|range| self.accesses(range.clone()))
+            .collect();
+        let is_shared = |name: &&String| {
+            let parts = accesses.iter().enumerate();
+            parts.clone().any(|(reader, (mentioned, _))| {
+                mentioned.contains(name)
+                    && parts
+                        .clone()
+                        .any(|(writer, (_, written))| writer != reader && written.contains(name))
+            })
+        };
+        self.variables().into_iter().filter(is_shared).collect()
+    }
+
+    /// the program run by the processor owning `range`
+    fn build(
+        &self,
+        range: Range<usize>,
+        entries: &[usize],
+        shared: &[&String],
+    ) -> Result<Program, SplitError> {
+        let program = self.program;
+        let mut part = Program {
+            blocks: vec![BasicBlock::default()],
+            temporaries: program.temporaries.clone(),
+            kept: program.kept.clone(),
+            labels: program.labels,
+        };
+        let pc = Operand::Variable(PC);
+        let id = |index: usize| Operand::from_number(index as f64);
+        let jump = |target, condition, lhs: &Operand, rhs: Operand| Instruction::Jump {
+            target,
+            condition,
+            lhs: lhs.clone(),
+            rhs,
+        };
+        let goto = |target| jump(target, Condition::Always, &id(0), id(0));
+
+        let start = part.new_label();
+        let mine = part.new_label();
+        let exit = part.new_label();
+        let heads: HashMap<usize, LabelId> = range
+            .clone()
+            .map(|index| match program.blocks[index].labels.first() {
+                Some(label) => (index, *label),
+                None => (index, part.new_label()),
+            })
+            .collect();
+        let (mentioned, written) = self.accesses(range.clone());
+        let slots = (1..).zip(shared);
+
+        // wait for one of the entries, then read the shared variables and go to that entry
+        part.place_label(start);
+        part.push(Instruction::new(command::read::Read {
+            dst: PC,
+            from: self.memory.arg().clone(),
+            at: String::new(0),
+        }));
+        let (last, others) = entries.split_last().expect("a part has an entry");
+        for entry in others {
+            part.push(jump(mine, Condition::Equal, &pc, id(*entry)));
+        }
+        part.push(jump(start, Condition::NotEqual, &pc, id(*last)));
+        part.place_label(mine);
+        for (slot, name) in slots.clone().filter(|(_, name)| mentioned.contains(*name)) {
+            part.push(Instruction::new(command::read::Read {
+                dst: (*name).clone(),
+                from: self.memory.arg().clone(),
+                at: String::new(slot),
+            }));
+        }
+        for entry in entries.iter().filter(|entry| **entry != range.start) {
+            part.push(jump(heads[entry], Condition::Equal, &pc, id(*entry)));
+        }
+
+        // blocks outside of `range` are reached by writing their id and leaving
+        let mut stubs: Vec<(usize, LabelId)> = vec![];
+        let mut target = |part: &mut Program, index: usize| match heads.get(&index) {
+            Some(head) => *head,
+            None => match stubs.iter().find(|(stub, _)| *stub == index) {
+                Some((_, label)) => *label,
+                None => {
+                    let label = part.new_label();
+                    stubs.push((index, label));
+                    label
+                }
+            },
+        };
+        let mut leaves = false;
+        for index in range.clone() {
+            let block = &program.blocks[index];
+            part.place_label(heads[&index]);
+            for label in block.labels.iter().skip(1) {
+                part.place_label(*label);
+            }
+            for instruction in &block.instructions {
+                let instruction = match instruction {
+                    Instruction::Jump {
+                        target: label,
+                        condition,
+                        lhs,
+                        rhs,
+                    } => Instruction::Jump {
+                        target: target(&mut part, self.label_blocks[label]),
+                        condition: *condition,
+                        lhs: lhs.clone(),
+                        rhs: rhs.clone(),
+                    },
+                    Instruction::Command {
+                        command: Command::End(..),
+                        ..
+                    } => goto(target(&mut part, 0)),
+                    Instruction::Address { result, label } => {
+                        Instruction::set(result.clone(), id(self.label_blocks[label]))
+                    }
+                    instruction if instruction.writes_counter() => {
+                        let address = instruction
+                            .as_set()
+                            .and_then(|(_, value)| value.as_variable())
+                            .ok_or(SplitError::ComputedJump)?;
+                        part.push(Instruction::set(
+                            pc.clone(),
+                            Operand::Variable(address.clone()),
+                        ));
+                        leaves = true;
+                        goto(exit)
+                    }
+                    instruction => instruction.clone(),
+                };
+                part.push(instruction);
+            }
+        }
+        if Self::falls_through(&program.blocks[range.end - 1]) {
+            let next = match range.end == program.blocks.len() {
+                true => 0,
+                false => range.end,
+            };
+            let next = target(&mut part, next);
+            part.push(goto(next));
+        }
+
+        for (position, (index, label)) in stubs.iter().enumerate() {
+            part.place_label(*label);
+            part.push(Instruction::set(pc.clone(), id(*index)));
+            if position + 1 < stubs.len() {
+                part.push(goto(exit));
+            }
+        }
+        if leaves || !stubs.is_empty() {
+            part.place_label(exit);
+            for (slot, name) in slots.filter(|(_, name)| written.contains(*name)) {
+                part.push(Instruction::new(command::write::Write {
+                    src: (*name).clone(),
+                    to: self.memory.arg().clone(),
+                    at: String::new(slot),
+                }));
+            }
+            part.push(Instruction::new(command::write::Write {
+                src: PC,
+                to: self.memory.arg().clone(),
+                at: String::new(0),
+            }));
+        }
+        Ok(part)
+    }
+}
+
+/// split `program` across processors of at most `limit` instructions, sharing the variables
+/// through `memory` of `capacity` slots
+///
+/// the blocks are taken in order, each processor getting as many as fit, and blocks longer than
+/// half a processor are cut first. `@counter` may only be set to a variable holding an address
+pub(crate) fn split(
+    program: &Program,
+    limit: usize,
+    memory: &String,
+    capacity: usize,
+) -> Result<Vec<Program>, SplitError> {
+    let mut cut = Program {
+        blocks: vec![],
+        ..program.clone()
+    };
+    for block in &program.blocks {
+        let mut chunks = block.instructions.chunks(limit / 2);
+        cut.blocks.push(BasicBlock {
+            labels: block.labels.clone(),
+            instructions: chunks.next().unwrap_or_default().to_vec(),
+        });
+        cut.blocks.extend(chunks.map(|chunk| BasicBlock {
+            labels: vec![],
+            instructions: chunk.to_vec(),
+        }));
+    }
+    let program = &cut;
+
+    let splitter = Splitter::new(program, memory);
+    // parts are measured sharing every variable, sharing less only makes them shorter
+    let everything = splitter.variables();
+    let mut parts: Vec<Range<usize>> = vec![];
+    let mut start = 0;
+    while start < program.blocks.len() {
+        let fits = |end: usize| -> Result<bool, SplitError> {
+            let part = splitter.build(start..end, &splitter.entries(start..end), &everything)?;
+            Ok(part.len() <= limit)
+        };
+        if !fits(start + 1)? {
+            let length = program.blocks[start].instructions.len();
+            return Err(SplitError::BlockTooLarge { length });
+        }
+        let mut end = start + 1;
+        while end < program.blocks.len() && fits(end + 1)? {
+            end += 1;
+        }
+        parts.push(start..end);
+        start = end;
+    }
+
+    let shared = splitter.shared(&parts);
+    if shared.len() + 1 > capacity {
+        return Err(SplitError::MemoryTooSmall {
+            needed: shared.len() + 1,
+            capacity,
+        });
+    }
+    let mut peephole = PassManager::new().with(Peephole);
+    parts
+        .into_iter()
+        .map(|range| {
+            let mut part = splitter.build(range.clone(), &splitter.entries(range), &shared)?;
+            peephole.run(&mut part);
+            Ok(part)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split;
+    use crate::{
+        limits::{ProcessorKind, SplitError},
+        processor::Processor,
+        types::{
+            building::{Building, MemoryCell},
+            number::Number,
+        },
+        String,
+    };
+
+    #[test]
+    fn split_parts() {
+        Processor::scope(|core| {
+            let a = core.from_mdt::<Number>("a");
+            let double = core.function(|_, [x]| x * 2);
+            core.for_range(0, a, 1, |core, i, _| {
+                let twice = double.call((i,));
+                core.unit().r#move(twice, a);
+            });
+            core.unit().r#move(double.call((a,)), 0);
+
+            let program = core.borrow_mut().optimize();
+            let parts = split(&program, 20, &String::Static("cell1"), 64).unwrap();
+            assert_eq!(parts.len(), 3);
+            // the return address is the id of a block, `end` goes back to the first one
+            assert_eq!(
+                parts[2].render(),
+                "read __pc cell1 0\n\
+                 jump 3 equal __pc 6\n\
+                 jump 0 notEqual __pc 7\n\
+                 read v1 cell1 2\n\
+                 read v2 cell1 3\n\
+                 jump 8 equal __pc 7\n\
+                 ucontrol move v1 0 0 0 0\n\
+                 jump 11 always 0 0\n\
+                 op mul v1 v1 2\n\
+                 set __pc v2\n\
+                 jump 12 always 0 0\n\
+                 set __pc 0\n\
+                 write v1 cell1 2\n\
+                 write __pc cell1 0\n"
+            );
+            assert_eq!(
+                split(&program, 20, &String::Static("cell1"), 3).unwrap_err(),
+                SplitError::MemoryTooSmall {
+                    needed: 4,
+                    capacity: 3
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn split_generate() {
+        Processor::scope(|core| {
+            let cell = core.from_mdt::<Building<MemoryCell>>("cell1");
+            core.split_with(cell);
+            for i in 0..ProcessorKind::MAX_INSTRUCTIONS + 1 {
+                core.unit().r#move(i as f64, 0);
+            }
+            let parts = core.generate_split().unwrap();
+            assert_eq!(parts.len(), 2);
+            assert!(parts[0].starts_with("read __pc cell1 0\njump 0 notEqual __pc 0\n"));
+            assert!(parts[1].starts_with("read __pc cell1 0\njump 0 notEqual __pc 1\n"));
+            assert!(parts[1].ends_with("set __pc 0\nwrite __pc cell1 0\n"));
+        });
+    }
+}
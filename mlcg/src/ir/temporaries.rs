@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use super::{Instruction, Operand, Pass, Program};
+use crate::String;
+
+/// how many times each variable is read and written in `program`
+pub(crate) fn count_accesses(
+    program: &Program,
+) -> (HashMap<String, usize>, HashMap<String, usize>) {
+    let mut reads = HashMap::new();
+    let mut writes = HashMap::new();
+    for instruction in program.instructions() {
+        let operands = [
+            (&mut reads, instruction.inputs()),
+            (&mut writes, instruction.outputs()),
+        ];
+        for (counts, operands) in operands {
+            for variable in operands.into_iter().filter_map(Operand::as_variable) {
+                *counts.entry(variable.clone()).or_default() += 1;
+            }
+        }
+    }
+    (reads, writes)
+}
+
+fn mentions(instruction: &Instruction, name: &String) -> bool {
+    instruction
+        .inputs()
+        .into_iter()
+        .chain(instruction.outputs())
+        .any(|operand| operand.as_variable() == Some(name))
+}
+
+/// write results straight into the variable they are copied to
+///
+/// a temporary written once and only read by a `set x temporary` later in the same block is
+/// replaced by `x`, and the `set` removed, as long as `x` is not used in between. this removes
+/// the copies made by [`crate::r#ref::Ref::save_as`] and the ones made when returning from a
+/// [`crate::function::Function`]
+#[derive(Debug, Default)]
+pub struct TemporaryElimination;
+
+impl TemporaryElimination {
+    /// the index of the instruction writing the temporary copied by the `set` at `index`
+    fn forwarded(
+        program: &Program,
+        block: usize,
+        index: usize,
+        reads: &HashMap<String, usize>,
+        writes: &HashMap<String, usize>,
+    ) -> Option<usize> {
+        let instructions = &program.blocks[block].instructions;
+        let (Operand::Variable(result), Operand::Variable(value)) = instructions[index].as_set()?
+        else {
+            return None;
+        };
+        if result == value
+            || !program.is_temporary(value)
+            || reads.get(value) != Some(&1)
+            || writes.get(value) != Some(&1)
+        {
+            return None;
+        }
+        let source = instructions[..index]
+            .iter()
+            .rposition(|instruction| mentions(instruction, value))?;
+        let written = instructions[source]
+            .outputs()
+            .into_iter()
+            .any(|output| output.as_variable() == Some(value));
+        let clobbered = instructions[source + 1..index]
+            .iter()
+            .any(|instruction| mentions(instruction, result))
+            || instructions[source]
+                .outputs()
+                .into_iter()
+                .any(|output| output.as_variable() == Some(result));
+        (written && !clobbered).then_some(source)
+    }
+}
+
+impl Pass for TemporaryElimination {
+    fn name(&self) -> &'static str {
+        "temporary-elimination"
+    }
+
+    fn run(&mut self, program: &mut Program) -> bool {
+        // the counts of other variables are not changed by forwarding a temporary
+        let (reads, writes) = count_accesses(program);
+        let mut changed = false;
+        for block in 0..program.blocks.len() {
+            let mut index = 0;
+            while index < program.blocks[block].instructions.len() {
+                let Some(source) = Self::forwarded(program, block, index, &reads, &writes) else {
+                    index += 1;
+                    continue;
+                };
+                let instructions = &mut program.blocks[block].instructions;
+                let set = instructions.remove(index);
+                let (result, value) = set.as_set().unwrap();
+                for output in instructions[source].outputs_mut() {
+                    if *output == *value {
+                        *output = result.clone();
+                    }
+                }
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::{PassManager, TemporaryElimination},
+        processor::Processor,
+        types::number::Number,
+    };
+
+    #[test]
+    fn save_as() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new().with(TemporaryElimination));
+            let a = core.from_mdt::<Number>("a");
+            let b = core.from_mdt::<Number>("b");
+
+            let c = (a + b).save_as("c");
+            // `d` is read between the `op` and the `set`, so it must not be written earlier
+            let d = core.from_mdt::<Number>("d");
+            let sum = c + 1;
+            core.unit().r#move(d, d);
+            let _ = sum.save_as("d");
+            // read twice
+            let twice = c * 2;
+            let _ = twice.save_as("e");
+            let _ = twice + 1;
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "op add c a b\n\
+                 op add v1 c 1\n\
+                 ucontrol move d d 0 0 0\n\
+                 set d v1\n\
+                 op mul v2 c 2\n\
+                 set e v2\n\
+                 op add v3 v2 1\n"
+            );
+        });
+    }
+}
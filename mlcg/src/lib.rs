@@ -6,13 +6,24 @@ pub mod command {
     mlcg_derive::commands!("src/commands.json");
 }
 
+#[macro_use]
+pub mod r#ref;
+
 pub mod abilities;
 pub mod env;
+mod error;
 pub mod eval;
+pub mod flow;
+pub mod function;
+pub mod ir;
+pub mod limits;
+pub mod locate;
 pub mod processor;
-#[macro_use]
-pub mod r#ref;
+pub mod radar;
+pub mod sensor;
 pub mod types;
 
 mod string;
 use string::String;
+
+pub use error::{Diagnostic, Error};
@@ -0,0 +1,184 @@
+//! what a processor can run: the number of instructions it holds and how many it runs per tick
+
+use crate::ir::Program;
+
+/// the kind of processor the program is generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ProcessorKind {
+    Micro,
+    #[default]
+    Logic,
+    Hyper,
+    /// the processor of map makers, its `@ipt` is the default one, which `setrate` changes
+    World,
+}
+
+impl ProcessorKind {
+    /// instructions a processor holds, the game drops the ones after
+    pub const MAX_INSTRUCTIONS: usize = 1000;
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProcessorKind::Micro => "micro-processor",
+            ProcessorKind::Logic => "logic-processor",
+            ProcessorKind::Hyper => "hyper-processor",
+            ProcessorKind::World => "world-processor",
+        }
+    }
+
+    /// the number of instructions the processor holds
+    pub fn limit(&self) -> usize {
+        Self::MAX_INSTRUCTIONS
+    }
+
+    /// instructions run per tick, the value of `@ipt`
+    pub fn ipt(&self) -> usize {
+        match self {
+            ProcessorKind::Micro => 2,
+            ProcessorKind::Logic => 8,
+            ProcessorKind::Hyper => 25,
+            ProcessorKind::World => 8,
+        }
+    }
+}
+
+/// the size of a generated program, and an estimate of the time it takes to run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub kind: ProcessorKind,
+    /// number of instructions
+    pub length: usize,
+    /// number of instructions of each command, the most used first
+    pub breakdown: Vec<(std::string::String, usize)>,
+    /// number of instructions on the longest path from the start of the program back to it, which
+    /// runs the body of every loop once
+    pub main_loop: usize,
+}
+
+impl Report {
+    pub(crate) fn new(kind: ProcessorKind, program: &Program) -> Self {
+        let mut breakdown: Vec<(std::string::String, usize)> = vec![];
+        for line in program.render().lines() {
+            let command = line.split(' ').next().unwrap_or_default();
+            match breakdown.iter_mut().find(|(name, _)| name == command) {
+                Some((_, count)) => *count += 1,
+                None => breakdown.push((command.to_owned(), 1)),
+            }
+        }
+        breakdown.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+        Self {
+            kind,
+            length: program.len(),
+            breakdown,
+            main_loop: program.longest_path(),
+        }
+    }
+
+    /// `true` if the program fits in the processor
+    pub fn fits(&self) -> bool {
+        self.length <= self.kind.limit()
+    }
+
+    /// estimate of the ticks one run of the main loop takes
+    pub fn ticks(&self) -> usize {
+        self.main_loop.div_ceil(self.kind.ipt())
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} instructions out of {} on a {}",
+            self.length,
+            self.kind.limit(),
+            self.kind.name()
+        )?;
+        for (command, count) in &self.breakdown {
+            writeln!(f, "  {command}: {count}")?;
+        }
+        write!(
+            f,
+            "main loop of up to {} instructions, {} ticks at {} instructions per tick",
+            self.main_loop,
+            self.ticks(),
+            self.kind.ipt()
+        )
+    }
+}
+
+/// the program can not be split across processors, see [`crate::processor::Processor::split_with`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitError {
+    /// a block of instructions without jumps does not fit in one processor on its own
+    BlockTooLarge { length: usize },
+    /// the memory does not hold the variables shared by the processors and the program counter
+    MemoryTooSmall { needed: usize, capacity: usize },
+    /// `@counter` is written with something else than a variable holding an address
+    ComputedJump,
+}
+
+impl std::fmt::Display for SplitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SplitError::BlockTooLarge { length } => {
+                write!(
+                    f,
+                    "a block of {length} instructions does not fit in a processor"
+                )
+            }
+            SplitError::MemoryTooSmall { needed, capacity } => write!(
+                f,
+                "{needed} slots are needed to split the program, the memory has {capacity}"
+            ),
+            SplitError::ComputedJump => {
+                write!(f, "a program writing `@counter` can not be split")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SplitError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{processor::Processor, types::number::Number, Error};
+
+    #[test]
+    fn limit() {
+        Processor::scope_for(ProcessorKind::Micro, |core| {
+            let a = core.from_mdt::<Number>("a");
+            core.for_range(0, 10, 1, |core, i, _| {
+                core.unit().r#move(i, a);
+            });
+            let report = core.report();
+            assert_eq!(report.length, 5);
+            assert_eq!(report.main_loop, 5);
+            assert_eq!(report.ticks(), 3);
+            assert_eq!(
+                report.breakdown,
+                [
+                    ("jump".into(), 2),
+                    ("op".into(), 1),
+                    ("set".into(), 1),
+                    ("ucontrol".into(), 1)
+                ]
+            );
+        });
+
+        Processor::scope(|core| {
+            for i in 0..ProcessorKind::MAX_INSTRUCTIONS + 1 {
+                core.unit().r#move(i as f64, 0);
+            }
+            let diagnostics = core.generate().unwrap_err();
+            let Error::LimitExceeded(report) = &diagnostics[0].error else {
+                panic!("{diagnostics:?}");
+            };
+            assert_eq!(report.length, 1001);
+            assert!(diagnostics[0]
+                .to_string()
+                .contains("1001 instructions out of 1000 on a logic-processor"));
+        });
+    }
+}
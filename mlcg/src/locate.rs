@@ -0,0 +1,130 @@
+//! `ulocate`, finding the closest ore, building, spawn or damaged block from the bound unit,
+//! wherever it is on the map
+
+use crate::{
+    command,
+    eval::Eval,
+    r#ref::Ref,
+    types::{boolean::Bool, building::Building, content::Item, number::Number, unit::Unit},
+    String,
+};
+
+/// the kind of building looked for by [`Ref::locate_building`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockGroup {
+    Core,
+    Storage,
+    Generator,
+    Turret,
+    Factory,
+    Repair,
+    Battery,
+    Reactor,
+}
+
+impl BlockGroup {
+    fn name(self) -> &'static str {
+        match self {
+            BlockGroup::Core => "core",
+            BlockGroup::Storage => "storage",
+            BlockGroup::Generator => "generator",
+            BlockGroup::Turret => "turret",
+            BlockGroup::Factory => "factory",
+            BlockGroup::Repair => "repair",
+            BlockGroup::Battery => "battery",
+            BlockGroup::Reactor => "reactor",
+        }
+    }
+}
+
+/// the result of `ulocate`, `x`, `y` and `building` are only set when `found` is `true`
+#[derive(Clone, Copy)]
+pub struct Located<'a> {
+    pub found: Ref<'a, Bool>,
+    pub x: Ref<'a, Number>,
+    pub y: Ref<'a, Number>,
+    /// `null` when locating an ore
+    pub building: Ref<'a, Building>,
+}
+
+impl<'a> Ref<'a, Unit> {
+    fn ulocate(
+        &self,
+        find: &'static str,
+        group: &'static str,
+        enemy: String,
+        ore: String,
+    ) -> Located<'a> {
+        let core = self.core;
+        let located = Located {
+            found: core.new_unnamed(),
+            x: core.new_unnamed(),
+            y: core.new_unnamed(),
+            building: core.new_unnamed(),
+        };
+        let command = command::ulocate::Ulocate {
+            find: String::Static(find),
+            group: String::Static(group),
+            enemy,
+            ore,
+            x: located.x.eval(),
+            y: located.y.eval(),
+            found: located.found.eval(),
+            building: located.building.eval(),
+        };
+        core.borrow_mut().push_command(command);
+        located
+    }
+
+    /// the closest ore of `item`
+    pub fn locate_ore(&self, item: impl Eval<'a, Item>) -> Located<'a> {
+        let ore = item.eval().eval();
+        self.ulocate("ore", "core", String::Static("0"), ore)
+    }
+
+    /// the closest building of `group`, of an enemy team if `enemy` is `true` and of the team
+    /// of the unit otherwise
+    pub fn locate_building(&self, group: BlockGroup, enemy: impl Eval<'a, Bool>) -> Located<'a> {
+        let enemy = enemy.eval().eval();
+        self.ulocate("building", group.name(), enemy, String::Static("0"))
+    }
+
+    /// the closest enemy spawn, a point where waves come from or an enemy core
+    pub fn locate_spawn(&self) -> Located<'a> {
+        self.ulocate("spawn", "core", String::Static("0"), String::Static("0"))
+    }
+
+    /// the closest damaged building of the team of the unit
+    pub fn locate_damaged(&self) -> Located<'a> {
+        self.ulocate("damaged", "core", String::Static("0"), String::Static("0"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::Processor;
+
+    #[test]
+    fn locate() {
+        Processor::scope(|core| {
+            let unit = core.unit();
+            let ore = unit.locate_ore(Item::Copper);
+            let _ = core.if_(ore.found, |_| {
+                unit.mine(ore.x, ore.y);
+            });
+            let core1 = unit.locate_building(BlockGroup::Core, false);
+            unit.item_drop(core1.building, 30);
+            let _unused = unit.locate_damaged();
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "ulocate ore core 0 @copper v0 v1 v2 v3\n\
+                 jump 3 equal v2 false\n\
+                 ucontrol mine v0 v1 0 0 0\n\
+                 ulocate building core false 0 v0 v1 v2 v3\n\
+                 ucontrol itemDrop v3 30 0 0 0\n"
+            );
+        });
+    }
+}
@@ -1,19 +1,76 @@
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    panic::Location,
+};
 
 use crate::{
-    command::Command,
+    command::{self, Command},
     eval::Eval,
+    flow::{Condition, Loop},
+    function::Frame,
+    ir::{self, PassManager, Program},
+    limits::{ProcessorKind, Report},
     r#ref::Ref,
-    types::{number::Number, unit::Unit, Type},
-    String,
+    types::{
+        building::{Building, Memory},
+        number::Number,
+        unit::Unit,
+        Type,
+    },
+    Diagnostic, Error, String,
 };
 
-#[derive(Default)]
-pub struct Processor {
+/// the program being built
+///
+/// a processor only exists inside of [`Processor::scope`], and `'id` is a lifetime unique to it:
+/// a [`Ref`] carries the brand of its processor, so refs of different processors can not be
+/// mixed, which is checked by the compiler
+///
+/// ```compile_fail
+/// # use mlcg::{processor::Processor, types::number::Number};
+/// Processor::scope(|a| {
+///     Processor::scope(|b| {
+///         let x = a.from_mdt::<Number>("x");
+///         let y = b.from_mdt::<Number>("y");
+///         let _ = x + y;
+///     })
+/// });
+/// ```
+pub struct Processor<'id> {
     pub(crate) inner: RefCell<RawProcessor>,
+    /// invariant, so a brand is never shortened into another one
+    _brand: PhantomData<fn(&'id ()) -> &'id ()>,
 }
 
-impl Processor {
+impl Processor<'_> {
+    /// build a program for a [`ProcessorKind::Logic`] in `body`
+    pub fn scope<R>(body: impl for<'id> FnOnce(&'id Processor<'id>) -> R) -> R {
+        Self::scope_for(ProcessorKind::default(), body)
+    }
+
+    /// build a program for `kind` in `body`
+    pub fn scope_for<R>(
+        kind: ProcessorKind,
+        body: impl for<'id> FnOnce(&'id Processor<'id>) -> R,
+    ) -> R {
+        let processor = Processor {
+            inner: RefCell::new(RawProcessor {
+                kind,
+                ..Default::default()
+            }),
+            _brand: PhantomData,
+        };
+        body(&processor)
+    }
+}
+
+impl<'id> Processor<'id> {
+    pub fn kind(&self) -> ProcessorKind {
+        self.borrow().kind
+    }
+
     pub(crate) fn borrow(&self) -> std::cell::Ref<'_, RawProcessor> {
         self.inner.borrow()
     }
@@ -22,7 +79,7 @@ impl Processor {
         self.inner.borrow_mut()
     }
 
-    pub(crate) fn make_ref<T: Type>(&self, idx: VariableIdx) -> Ref<'_, T> {
+    pub(crate) fn make_ref<T: Type>(&'id self, idx: VariableIdx) -> Ref<'id, T> {
         Ref {
             core: self,
             idx,
@@ -30,101 +87,162 @@ impl Processor {
         }
     }
 
-    pub fn new_unnamed<T: Type>(&self) -> Ref<'_, T> {
+    pub fn new_unnamed<T: Type>(&'id self) -> Ref<'id, T> {
         let raw = &mut self.borrow_mut();
         let temp_name = raw.alloc_name();
         let raw = raw.new_variable(temp_name);
         self.make_ref(raw)
     }
 
-    pub fn new_uninit<T: Type>(&self, name: impl Eval<String>) -> Ref<'_, T> {
+    pub fn new_uninit<T: Type>(&'id self, name: impl Eval<'id, String>) -> Ref<'id, T> {
         let raw = &mut self.borrow_mut();
         let var = raw.new_variable(name);
         self.make_ref(var)
     }
 
-    pub(crate) fn is_same_core(&self, rhs: &Self) -> bool {
-        (self as *const Processor).eq(&(rhs as _))
+    /// record `error`, made at the call site of the builder
+    #[track_caller]
+    pub(crate) fn error(&self, error: Error) {
+        self.borrow_mut().diagnostics.push(Diagnostic::new(error));
     }
 
     /// # Note
     ///
     /// the `@unit` may not binded yet
-    pub fn unit(&self) -> Ref<'_, Unit> {
+    pub fn unit(&'id self) -> Ref<'id, Unit> {
         self.make_ref(VariableIdx::AT_UNIT_IDX)
     }
 
     /// `@thisx` process variable
-    pub fn thisx(&self) -> Ref<'_, Number> {
+    pub fn thisx(&'id self) -> Ref<'id, Number> {
         self.make_ref(VariableIdx::AT_THISX_IDX)
     }
 
     /// `@thisy` process variable
-    pub fn thisy(&self) -> Ref<'_, Number> {
+    pub fn thisy(&'id self) -> Ref<'id, Number> {
         self.make_ref(VariableIdx::AT_THISY_IDX)
     }
 
     /// `@counter` process variable
-    pub fn counter(&self) -> Ref<'_, Number> {
+    pub fn counter(&'id self) -> Ref<'id, Number> {
         self.make_ref(VariableIdx::AT_COUNTER_IDX)
     }
 
     /// `@links` process variable
-    pub fn links(&self) -> Ref<'_, Number> {
+    pub fn links(&'id self) -> Ref<'id, Number> {
         self.make_ref(VariableIdx::AT_LINKS_IDX)
     }
 
     /// `@ipt` process variable
-    pub fn ipt(&self) -> Ref<'_, Number> {
+    pub fn ipt(&'id self) -> Ref<'id, Number> {
         self.make_ref(VariableIdx::AT_IPT_IDX)
     }
 
     /// `@time` process variable
-    pub fn time(&self) -> Ref<'_, Number> {
+    pub fn time(&'id self) -> Ref<'id, Number> {
         self.make_ref(VariableIdx::AT_IPT_IDX)
     }
 
     /// `@tick` process variable
-    pub fn tick(&self) -> Ref<'_, Number> {
+    pub fn tick(&'id self) -> Ref<'id, Number> {
         self.make_ref(VariableIdx::AT_TICK_IDX)
     }
 
     /// `@mapw` process variable
-    pub fn mapw(&self) -> Ref<'_, Number> {
+    pub fn mapw(&'id self) -> Ref<'id, Number> {
         self.make_ref(VariableIdx::AT_MAPW_IDX)
     }
 
     /// `@maph` process variable
-    pub fn maph(&self) -> Ref<'_, Number> {
+    pub fn maph(&'id self) -> Ref<'id, Number> {
         self.make_ref(VariableIdx::AT_MAPH_IDX)
     }
 
-    // pub fn linkeds(&self) -> impl Iterator<Item = (Ref<'_, Number>, Ref<'_, Building>)> {
-    //     let max = self.links();
-    //     let mut counter = self.new_unnamed::<Number>();
-
-    //     counter %= max;
+    /// the building linked at `index`, `null` if `index` is out of `0..@links`
+    #[doc(alias = "getlink")]
+    pub fn link(&'id self, index: impl Eval<'id, Number>) -> Ref<'id, Building> {
+        let result = self.new_unnamed::<Building>();
+        let command = command::getlink::Getlink {
+            result: result.eval(),
+            index: index.eval().eval(),
+        };
+        self.borrow_mut().push_command(command);
+        result
+    }
 
-    //     todo!()
-    // }
+    /// run `body` for every linked building, with its index
+    pub fn for_each_link(
+        &'id self,
+        body: impl FnOnce(&'id Processor<'id>, Ref<'id, Number>, Ref<'id, Building>, &Loop<'id>),
+    ) {
+        self.for_range(0, self.links(), 1, |core, index, control| {
+            let building = core.link(index);
+            body(core, index, building, control);
+        });
+    }
 
-    pub fn from_mdt<T: Type>(&self, name: impl Eval<String>) -> Ref<'_, T> {
+    pub fn from_mdt<T: Type>(&'id self, name: impl Eval<'id, String>) -> Ref<'id, T> {
         let name = name.eval();
         let idx = self.borrow_mut().new_variable(name);
         self.make_ref(idx)
     }
 
-    pub fn generate(self) -> std::string::String {
-        self.inner.borrow().generate()
+    /// replace the passes run by [`Processor::generate`], see [`PassManager::default`] for the
+    /// passes run otherwise
+    pub fn set_passes(&self, passes: PassManager) {
+        self.borrow_mut().passes = passes;
+    }
+
+    /// the size of the program and the time it takes to run, as generated by
+    /// [`Processor::generate`]
+    pub fn report(&self) -> Report {
+        let mut raw = self.borrow_mut();
+        let program = raw.optimize();
+        Report::new(raw.kind, &program)
+    }
+
+    /// the mlog code of the program, or every misuse of the builder and an error if the program
+    /// does not fit in the processor
+    #[track_caller]
+    pub fn generate(&self) -> Result<std::string::String, Vec<Diagnostic>> {
+        self.inner.borrow_mut().generate()
+    }
+
+    /// split the program across processors sharing `memory` when it does not fit in one, see
+    /// [`Processor::generate_split`]
+    ///
+    /// the memory holds the id of the code to run and the variables used by more than one
+    /// processor, it must not be used for anything else
+    pub fn split_with<M: Memory>(&self, memory: Ref<'id, Building<M>>) {
+        self.borrow_mut().split = Some((memory.eval(), M::CAPACITY));
+    }
+
+    /// the mlog code of every processor running the program, a single one if it fits
+    ///
+    /// every processor is linked to the memory given to [`Processor::split_with`] and to the same
+    /// buildings, with the same names. only one processor runs at a time: when it leaves its part
+    /// of the program, it writes the shared variables to the memory and then the id of the code
+    /// to run next, which the processor owning that code waits for. the memory only holds numbers,
+    /// so units and buildings do not survive the handoff and must be found again
+    #[track_caller]
+    pub fn generate_split(&self) -> Result<Vec<std::string::String>, Vec<Diagnostic>> {
+        self.inner.borrow_mut().generate_split()
     }
 
-    pub fn write_to(self, mut target: impl std::io::Write) -> std::io::Result<()> {
-        let code = self.inner.borrow().generate();
+    /// write the mlog code of the program to `target`, the diagnostics of
+    /// [`Processor::generate`] are an [`std::io::ErrorKind::InvalidData`] error
+    #[track_caller]
+    pub fn write_to(&self, mut target: impl std::io::Write) -> std::io::Result<()> {
+        let code = self.inner.borrow_mut().generate().map_err(|diagnostics| {
+            let diagnostics: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+            std::io::Error::new(std::io::ErrorKind::InvalidData, diagnostics.join("\n"))
+        })?;
         target.write_all(code.as_bytes())?;
         target.flush()
     }
 
-    pub fn write_to_stdout(self) {
+    #[track_caller]
+    pub fn write_to_stdout(&self) {
         self.write_to(std::io::stdout()).unwrap();
     }
 }
@@ -134,35 +252,179 @@ pub(crate) struct RawProcessor {
     pub main: Block,
     pub appends: Vec<Block>,
     pub variables: Vec<String>,
+    /// names allocated by [`RawProcessor::alloc_name`]
+    pub temporaries: HashSet<String>,
     pub seq: usize,
+    pub labels: usize,
+    /// blocks being built by closures, innermost last
+    pub scopes: Vec<Block>,
+    /// functions whose body is being built, innermost last
+    pub frames: Vec<Frame>,
+    /// variables marked by [`Ref::keep_alive`]
+    pub kept: HashSet<String>,
+    pub passes: PassManager,
+    pub kind: ProcessorKind,
+    /// errors made while building the program
+    pub diagnostics: Vec<Diagnostic>,
+    /// memory given to [`Processor::split_with`], and its capacity
+    pub split: Option<(String, usize)>,
+    /// the call site which created each label
+    pub label_locations: Vec<&'static Location<'static>>,
 }
 
 impl RawProcessor {
     pub(crate) fn alloc_name(&mut self) -> String {
         let idx = self.seq;
         self.seq += 1;
-        format!("v{}", idx).eval()
+        let name: String = format!("v{}", idx).eval();
+        self.temporaries.insert(name.clone());
+        name
+    }
+
+    pub(crate) fn is_temporary(&self, name: &String) -> bool {
+        self.temporaries.contains(name)
     }
 
-    pub(crate) fn new_variable(&mut self, name: impl Eval<String>) -> VariableIdx {
+    pub(crate) fn new_variable<'id>(&mut self, name: impl Eval<'id, String>) -> VariableIdx {
         let name = name.eval();
         self.variables.push(name);
         VariableIdx(self.variables.len() - 1)
     }
 
-    pub(crate) fn push_command(&mut self, command: impl Into<Command>) {
-        self.main.commands.push(command.into());
+    #[track_caller]
+    pub(crate) fn alloc_label(&mut self) -> LabelId {
+        let label = LabelId(self.labels);
+        self.labels += 1;
+        self.label_locations.push(Location::caller());
+        label
     }
 
-    pub fn generate(&self) -> std::string::String {
-        use std::fmt::Write;
-        self.main
-            .commands
+    /// labels which are jumped to or whose address is taken in `lowered`, but never placed
+    pub(crate) fn unplaced_labels(&self, lowered: &[Statement]) -> Vec<LabelId> {
+        let placed: HashSet<LabelId> = lowered
             .iter()
-            .fold(std::string::String::new(), |mut buffer, command| {
-                buffer.write_fmt(format_args!("{command}\n")).ok();
-                buffer
+            .filter_map(|statement| match statement {
+                Statement::Label(label) => Some(*label),
+                _ => None,
             })
+            .collect();
+        let mut unplaced = vec![];
+        for statement in lowered {
+            let (Statement::Jump { target: label, .. } | Statement::Address { label, .. }) =
+                statement
+            else {
+                continue;
+            };
+            if !placed.contains(label) && !unplaced.contains(label) {
+                unplaced.push(*label);
+            }
+        }
+        unplaced
+    }
+
+    /// the diagnostics recorded while building, and one for every label which is never placed
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let unplaced = self.unplaced_labels(&self.lower());
+        let unplaced = unplaced.into_iter().map(|label| Diagnostic {
+            error: Error::UnplacedLabel,
+            location: self.label_locations[label.0],
+        });
+        self.diagnostics.iter().cloned().chain(unplaced).collect()
+    }
+
+    /// the block at `depth`, `0` is `main` and the innermost scope is `self.scopes.len()`
+    pub(crate) fn block_at(&mut self, depth: usize) -> &mut Block {
+        match depth {
+            0 => &mut self.main,
+            depth => &mut self.scopes[depth - 1],
+        }
+    }
+
+    pub(crate) fn current_block(&mut self) -> &mut Block {
+        self.block_at(self.scopes.len())
+    }
+
+    pub(crate) fn push_statement(&mut self, statement: Statement) {
+        self.current_block().statements.push(statement);
+    }
+
+    pub(crate) fn push_command(&mut self, command: impl Into<Command>) {
+        self.push_statement(Statement::Command(command.into()));
+    }
+
+    /// `main` first, then every block in `appends`
+    fn blocks(&self) -> impl Iterator<Item = &Block> {
+        std::iter::once(&self.main).chain(&self.appends)
+    }
+
+    /// flatten every block into commands, labels and jumps
+    ///
+    /// `main` is terminated by `end` when blocks are appended, so it never runs into them
+    pub(crate) fn lower(&self) -> Vec<Statement> {
+        let uses = self.count_uses();
+        let mut lowered = vec![];
+        self.main.lower_into(self, &uses, &mut lowered);
+        if !self.appends.is_empty() {
+            lowered.push(Statement::Command(command::end::End {}.into()));
+        }
+        for block in &self.appends {
+            block.lower_into(self, &uses, &mut lowered);
+        }
+        lowered
+    }
+
+    /// count the occurrences of every name as an argument in every block
+    ///
+    /// fusing a comparison into a jump moves its operands without changing their counts, and the
+    /// fused result is never looked up again, so the counts hold for the whole lowering
+    fn count_uses(&self) -> HashMap<String, usize> {
+        let mut uses = HashMap::new();
+        for block in self.blocks() {
+            block.count_uses(&mut uses);
+        }
+        uses
+    }
+
+    /// lower every block and run the passes over the resulting [`Program`]
+    pub(crate) fn optimize(&mut self) -> Program {
+        let mut program = Program::new(self);
+        self.passes.run(&mut program);
+        program
+    }
+
+    #[track_caller]
+    pub fn generate(&mut self) -> Result<std::string::String, Vec<Diagnostic>> {
+        let mut diagnostics = self.diagnostics();
+        let program = self.optimize();
+        if program.len() > self.kind.limit() {
+            let report = Report::new(self.kind, &program);
+            diagnostics.push(Diagnostic::new(Error::LimitExceeded(report)));
+        }
+        match diagnostics.is_empty() {
+            true => Ok(program.render()),
+            false => Err(diagnostics),
+        }
+    }
+
+    #[track_caller]
+    pub fn generate_split(&mut self) -> Result<Vec<std::string::String>, Vec<Diagnostic>> {
+        let mut diagnostics = self.diagnostics();
+        let program = self.optimize();
+        let parts = match &self.split {
+            _ if program.len() <= self.kind.limit() => Ok(vec![program]),
+            None => Err(Error::LimitExceeded(Report::new(self.kind, &program))),
+            Some((memory, capacity)) => {
+                ir::split(&program, self.kind.limit(), memory, *capacity).map_err(Error::Split)
+            }
+        };
+        match parts {
+            Ok(parts) if diagnostics.is_empty() => Ok(parts.iter().map(Program::render).collect()),
+            Ok(..) => Err(diagnostics),
+            Err(error) => {
+                diagnostics.push(Diagnostic::new(error));
+                Err(diagnostics)
+            }
+        }
     }
 
     pub(crate) const UNIT: String = String::Static("@unit");
@@ -245,21 +507,211 @@ impl std::ops::Index<VariableIdx> for RawProcessor {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct Block {
-    commands: Vec<Command>,
+    pub(crate) statements: Vec<Statement>,
+}
+
+impl Block {
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    fn count_uses(&self, uses: &mut HashMap<String, usize>) {
+        for statement in &self.statements {
+            let names = match statement {
+                Statement::Command(command) => command.args(),
+                Statement::Label(..) => vec![],
+                Statement::Address { result, .. } => vec![result],
+                Statement::Jump { lhs, rhs, .. } => vec![lhs, rhs],
+                Statement::If {
+                    condition,
+                    then,
+                    otherwise,
+                    ..
+                } => {
+                    then.count_uses(uses);
+                    otherwise.count_uses(uses);
+                    vec![condition]
+                }
+            };
+            for name in names {
+                *uses.entry(name.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    fn lower_into(
+        &self,
+        raw: &RawProcessor,
+        uses: &HashMap<String, usize>,
+        lowered: &mut Vec<Statement>,
+    ) {
+        for (index, statement) in self.statements.iter().enumerate() {
+            let Statement::If {
+                condition,
+                then,
+                otherwise,
+                otherwise_label,
+                end_label,
+            } = statement
+            else {
+                lowered.push(statement.clone());
+                continue;
+            };
+
+            let skip = if otherwise.is_empty() {
+                *end_label
+            } else {
+                *otherwise_label
+            };
+            let fused = index
+                .checked_sub(1)
+                .and_then(|prev| self.fuse_condition(raw, uses, prev, condition));
+            let jump = match fused {
+                Some((condition, lhs, rhs)) => {
+                    lowered.pop();
+                    Statement::Jump {
+                        target: skip,
+                        condition,
+                        lhs,
+                        rhs,
+                    }
+                }
+                None => Statement::Jump {
+                    target: skip,
+                    condition: Condition::Equal,
+                    lhs: condition.clone(),
+                    rhs: String::Static("false"),
+                },
+            };
+            lowered.push(jump);
+
+            then.lower_into(raw, uses, lowered);
+            if !otherwise.is_empty() {
+                lowered.push(Statement::Jump {
+                    target: *end_label,
+                    condition: Condition::Always,
+                    lhs: String::Static("0"),
+                    rhs: String::Static("0"),
+                });
+                lowered.push(Statement::Label(*otherwise_label));
+                otherwise.lower_into(raw, uses, lowered);
+            }
+            lowered.push(Statement::Label(*end_label));
+        }
+    }
+
+    /// the negated jump condition for `condition`, if the statement at `prev` is a comparison
+    /// `op` whose result is `condition` and is not used anywhere else
+    fn fuse_condition(
+        &self,
+        raw: &RawProcessor,
+        uses: &HashMap<String, usize>,
+        prev: usize,
+        condition: &String,
+    ) -> Option<(Condition, String, String)> {
+        let Statement::Command(Command::Op(op)) = &self.statements[prev] else {
+            return None;
+        };
+        if op.result != *condition
+            || !raw.is_temporary(condition)
+            || uses.get(condition) != Some(&2)
+        {
+            return None;
+        }
+        let negated = Condition::from_name(&op.op)?.negate()?;
+        Some((negated, op.lhs.clone(), op.rhs.clone()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Statement {
+    Command(Command),
+    /// marks the position of a [`Label`], emits nothing
+    Label(LabelId),
+    /// `jump`, whose target is resolved in [`RawProcessor::generate`]
+    Jump {
+        target: LabelId,
+        condition: Condition,
+        lhs: String,
+        rhs: String,
+    },
+    /// `set` of the address of `label`, resolved in [`RawProcessor::generate`]
+    Address {
+        result: String,
+        label: LabelId,
+    },
+    /// lowered to `jump`s in [`RawProcessor::generate`]
+    If {
+        condition: String,
+        then: Block,
+        otherwise: Block,
+        otherwise_label: LabelId,
+        end_label: LabelId,
+    },
+}
+
+/// a position in the generated code, usable as a `jump` target before it is placed
+///
+/// branded like a [`Ref`], so a label of another processor can not be placed or jumped to
+///
+/// ```compile_fail
+/// # use mlcg::processor::Processor;
+/// Processor::scope(|a| {
+///     Processor::scope(|b| {
+///         let label = a.label();
+///         b.goto(label);
+///     })
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Label<'id> {
+    pub(crate) id: LabelId,
+    _brand: PhantomData<fn(&'id ()) -> &'id ()>,
+}
+
+impl Label<'_> {
+    pub(crate) fn new(id: LabelId) -> Self {
+        Self {
+            id,
+            _brand: PhantomData,
+        }
+    }
 }
 
+/// a [`Label`] once the program is lowered, without the brand of its processor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LabelId(pub(crate) usize);
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn for_each_link() {
+        Processor::scope(|core| {
+            core.for_each_link(|core, _, building, _| core.unit().item_drop(building, 10));
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "set v0 0\n\
+                 jump 0 greaterThanEq v0 @links\n\
+                 getlink v1 v0\n\
+                 ucontrol itemDrop v1 10 0 0 0\n\
+                 op add v0 v0 1\n\
+                 jump 1 always 0 0\n"
+            );
+        });
+    }
+
     #[test]
     fn test_name() {
-        let core = Processor::default();
-        let mut a = core.new_uninit::<Number>("abcd");
-        a += 1;
+        Processor::scope(|core| {
+            let mut a = core.new_uninit::<Number>("abcd");
+            a += 1;
 
-        core.write_to_stdout();
+            core.write_to_stdout();
+        });
     }
 }
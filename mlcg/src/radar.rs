@@ -0,0 +1,156 @@
+//! `radar` and `uradar`, finding the unit in range of a building or of the bound unit which
+//! matches every [`Filter`] and comes first by a [`Sort`]
+//!
+//! the result is a [`Unit`], a [`crate::abilities::Target`] for `targetp` and `shootp`
+
+use crate::{
+    command,
+    eval::Eval,
+    r#ref::Ref,
+    types::{
+        boolean::Bool,
+        building::{Building, Buildings},
+        unit::Unit,
+    },
+    String,
+};
+
+/// which units are found, the result matches all three filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Filter {
+    #[default]
+    Any,
+    Enemy,
+    Ally,
+    /// units controlled by a player
+    Player,
+    /// units with a weapon
+    Attacker,
+    Flying,
+    Boss,
+    Ground,
+}
+
+impl Filter {
+    fn name(self) -> &'static str {
+        match self {
+            Filter::Any => "any",
+            Filter::Enemy => "enemy",
+            Filter::Ally => "ally",
+            Filter::Player => "player",
+            Filter::Attacker => "attacker",
+            Filter::Flying => "flying",
+            Filter::Boss => "boss",
+            Filter::Ground => "ground",
+        }
+    }
+}
+
+/// which of the units found is the result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Sort {
+    #[default]
+    Distance,
+    Health,
+    Shield,
+    Armor,
+    MaxHealth,
+}
+
+impl Sort {
+    fn name(self) -> &'static str {
+        match self {
+            Sort::Distance => "distance",
+            Sort::Health => "health",
+            Sort::Shield => "shield",
+            Sort::Armor => "armor",
+            Sort::MaxHealth => "maxHealth",
+        }
+    }
+}
+
+impl<'a, B: Buildings> Ref<'a, Building<B>> {
+    /// the unit in range of the building matching `filters`, the closest or the one with the
+    /// most of `sort` if `order` is `true`, the farthest or the one with the least otherwise
+    ///
+    /// `null` if there is none, or if the building has no range, like anything but a turret
+    pub fn radar(
+        &self,
+        filters: [Filter; 3],
+        sort: Sort,
+        order: impl Eval<'a, Bool>,
+    ) -> Ref<'a, Unit> {
+        let result = self.core.new_unnamed();
+        let [target1, target2, target3] = filters.map(|filter| String::Static(filter.name()));
+        let command = command::radar::Radar {
+            target1,
+            target2,
+            target3,
+            sort: String::Static(sort.name()),
+            from: self.eval(),
+            order: order.eval().eval(),
+            result: result.eval(),
+        };
+        self.core.borrow_mut().push_command(command);
+        result
+    }
+}
+
+impl<'a> Ref<'a, Unit> {
+    /// like [`Ref::radar`], in range of the bound unit
+    pub fn uradar(
+        &self,
+        filters: [Filter; 3],
+        sort: Sort,
+        order: impl Eval<'a, Bool>,
+    ) -> Ref<'a, Unit> {
+        let result = self.core.new_unnamed();
+        let [target1, target2, target3] = filters.map(|filter| String::Static(filter.name()));
+        let command = command::uradar::Uradar {
+            target1,
+            target2,
+            target3,
+            sort: String::Static(sort.name()),
+            from: String::Static("0"),
+            order: order.eval().eval(),
+            result: result.eval(),
+        };
+        self.core.borrow_mut().push_command(command);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{processor::Processor, types::building::AnyBuilding};
+
+    #[test]
+    fn radar() {
+        Processor::scope(|core| {
+            let turret = core.from_mdt::<Building<AnyBuilding>>("ripple1");
+            let target = turret.radar(
+                [Filter::Enemy, Filter::Ground, Filter::Any],
+                Sort::Health,
+                false,
+            );
+            turret.shootp(target, true);
+
+            let unit = core.unit();
+            let escorted = unit.uradar(
+                [Filter::Ally, Filter::Boss, Filter::Any],
+                Sort::Distance,
+                true,
+            );
+            unit.targetp(escorted, false);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "radar enemy ground any health ripple1 false v0\n\
+                 control shootp ripple1 v0 true 0 0\n\
+                 uradar ally boss any distance 0 true v0\n\
+                 ucontrol targetp v0 false 0 0 0\n"
+            );
+        });
+    }
+}
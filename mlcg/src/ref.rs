@@ -2,7 +2,7 @@ use std::marker::PhantomData;
 
 use crate::{
     command,
-    eval::{Eval, WithCore},
+    eval::Eval,
     processor::{Processor, VariableIdx},
     types::Type,
     String,
@@ -12,31 +12,17 @@ pub struct Ref<'a, T>
 where
     T: Type,
 {
-    pub(crate) core: &'a Processor,
+    pub(crate) core: &'a Processor<'a>,
     pub(crate) idx: VariableIdx,
     pub(crate) _type: PhantomData<T>,
 }
 
-macro_rules! assert_same_core {
-    ($($refs:tt),*) => {
-        assert_same_core!(@rec $($refs),*)
-    };
-    (@rec $a:tt) => {};
-    (@rec $a:tt, $b:tt $(,$rest:tt)*) => {
-        match ($crate::eval::WithCore::core(&$a), $crate::eval::WithCore::core(&$b)) {
-            (Some(a_core), Some(b_core)) => assert!(a_core.is_same_core(b_core), "refernces must be in same processor!"),
-            _ => {}
-        }
-        assert_same_core!(@rec $b $(,$rest)*)
-    }
-}
-
 impl<'a, T> Ref<'a, T>
 where
     T: Type,
 {
     #[doc(alias = "set")]
-    pub fn save_as(&self, name: impl Eval<String>) -> Self {
+    pub fn save_as(&self, name: impl Eval<'a, String>) -> Self {
         let result = {
             let name = name.eval();
             if name.is_empty() {
@@ -57,7 +43,7 @@ where
         self.core.make_ref(result)
     }
 
-    pub fn set_to(&self, value: impl Eval<T>) -> Self {
+    pub fn set_to(&self, value: impl Eval<'a, T>) -> Self {
         let command = command::set::Set {
             result: self.core.borrow()[self.idx].clone(),
             value: value.eval().eval(),
@@ -66,6 +52,16 @@ where
         *self
     }
 
+    /// keep every write to the variable, even if this processor never reads it
+    ///
+    /// writes to variables which are never read are removed when generating, mark the variables
+    /// other processors or players read from this one
+    pub fn keep_alive(self) -> Self {
+        let name = self.core.borrow()[self.idx].clone();
+        self.core.borrow_mut().kept.insert(name);
+        self
+    }
+
     pub(crate) fn cast<T2: Type>(self) -> Ref<'a, T2> {
         Ref {
             core: self.core,
@@ -86,15 +82,9 @@ where
 
 impl<'a, T> Copy for Ref<'a, T> where T: Type {}
 
-impl<'a, T: Type> WithCore for Ref<'a, T> {
-    fn core(&self) -> Option<&Processor> {
-        Some(self.core)
-    }
-}
-
-impl<'a, T, U> Eval<U> for Ref<'a, T>
+impl<'a, T, U> Eval<'a, U> for Ref<'a, T>
 where
-    T: Eval<U> + Type,
+    T: Eval<'a, U> + Type,
 {
     default fn eval(self) -> U {
         T::from_name(self.core.borrow()[self.idx].clone()).eval()
@@ -109,23 +99,25 @@ mod tests {
 
     #[test]
     fn shoot() {
-        let core = Processor::default();
-        let at_unit = core.unit();
-        at_unit.target(core.thisx(), core.thisy(), true);
+        Processor::scope(|core| {
+            let at_unit = core.unit();
+            at_unit.target(core.thisx(), core.thisy(), true);
 
-        let _awa = at_unit.save_as("awa");
-        let awa = core.from_mdt::<Unit>("awa");
-        awa.bind().target(core.thisx(), core.thisy(), true);
+            let _awa = at_unit.save_as("awa");
+            let awa = core.from_mdt::<Unit>("awa");
+            awa.bind().target(core.thisx(), core.thisy(), true);
 
-        core.write_to_stdout();
+            core.write_to_stdout();
+        });
     }
 
     #[test]
     fn rebind() {
-        let core = Processor::default();
-        // so many bind :D
-        let unit = core.unit_bind::<Dagger>().cast_unit(Dagger);
-        core.bind(unit).bind().bind().bind().bind().bind().bind();
-        unit.bind().bind().bind().bind().bind().bind().bind().bind();
+        Processor::scope(|core| {
+            // so many bind :D
+            let unit = core.unit_bind::<Dagger>().cast_unit(Dagger);
+            core.bind(unit).bind().bind().bind().bind().bind().bind();
+            unit.bind().bind().bind().bind().bind().bind().bind().bind();
+        });
     }
 }
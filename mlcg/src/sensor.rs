@@ -0,0 +1,198 @@
+//! properties read with `sensor`, each one is a type whose [`Property::Output`] depends on what
+//! is sensed, `unit.sensor(Health)` is a [`Number`] while `unit.sensor(Dead)` is a [`Bool`]
+//!
+//! an [`Item`] or a [`Liquid`] is a property too, sensing the amount held
+
+use crate::{
+    command,
+    eval::Eval,
+    r#ref::Ref,
+    types::{
+        boolean::Bool,
+        building::{Building, Buildings},
+        content::{Block, Content, Item, Liquid, UnitType},
+        number::Number,
+        unit::{Unit, Units},
+        Type,
+    },
+    String,
+};
+
+/// a property which can be sensed from a `T`
+pub trait Property<T> {
+    type Output: Type;
+
+    /// the name of the property, like `@health`
+    fn name(self) -> String;
+}
+
+impl<'a, U: Units> Ref<'a, Unit<U>> {
+    /// read `property` of the unit
+    pub fn sensor<P: Property<Unit<U>>>(&self, property: P) -> Ref<'a, P::Output> {
+        sense(*self, property)
+    }
+}
+
+impl<'a, B: Buildings> Ref<'a, Building<B>> {
+    /// read `property` of the building
+    pub fn sensor<P: Property<Building<B>>>(&self, property: P) -> Ref<'a, P::Output> {
+        sense(*self, property)
+    }
+}
+
+fn sense<T: Type, P: Property<T>>(target: Ref<'_, T>, property: P) -> Ref<'_, P::Output> {
+    let result = target.core.new_unnamed();
+    let command = command::sensor::Sensor {
+        result: result.eval(),
+        target: target.eval(),
+        property: property.name(),
+    };
+    target.core.borrow_mut().push_command(command);
+    result
+}
+
+macro_rules! properties {
+    ($(
+        $(#[$attr:meta])* $property:ident => $name:literal $(, $target:ident : $output:ty)+ ;
+    )*) => {$(
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy)]
+        pub struct $property;
+
+        $(
+        properties!(@impl $property, $name, $target, $output);
+        )+
+    )*};
+    (@impl $property:ident, $name:literal, Unit, $output:ty) => {
+        impl<U: Units> Property<Unit<U>> for $property {
+            type Output = $output;
+
+            fn name(self) -> String {
+                String::Static(concat!('@', $name))
+            }
+        }
+    };
+    (@impl $property:ident, $name:literal, Building, $output:ty) => {
+        impl<B: Buildings> Property<Building<B>> for $property {
+            type Output = $output;
+
+            fn name(self) -> String {
+                String::Static(concat!('@', $name))
+            }
+        }
+    };
+}
+
+properties! {
+    TotalItems       => "totalItems",       Unit: Number, Building: Number;
+    /// the item held or stored the most, `null` when there is none
+    FirstItem        => "firstItem",        Unit: Item, Building: Item;
+    TotalLiquids     => "totalLiquids",     Building: Number;
+    TotalPower       => "totalPower",       Building: Number;
+    ItemCapacity     => "itemCapacity",     Unit: Number, Building: Number;
+    LiquidCapacity   => "liquidCapacity",   Building: Number;
+    PowerCapacity    => "powerCapacity",    Building: Number;
+    PowerNetStored   => "powerNetStored",   Building: Number;
+    PowerNetCapacity => "powerNetCapacity", Building: Number;
+    PowerNetIn       => "powerNetIn",       Building: Number;
+    PowerNetOut      => "powerNetOut",      Building: Number;
+    Ammo             => "ammo",             Unit: Number, Building: Number;
+    AmmoCapacity     => "ammoCapacity",     Unit: Number, Building: Number;
+    Health           => "health",           Unit: Number, Building: Number;
+    MaxHealth        => "maxHealth",        Unit: Number, Building: Number;
+    Heat             => "heat",             Building: Number;
+    Shield           => "shield",           Unit: Number;
+    Armor            => "armor",            Unit: Number, Building: Number;
+    Efficiency       => "efficiency",       Building: Number;
+    Progress         => "progress",         Unit: Number, Building: Number;
+    Timescale        => "timescale",        Building: Number;
+    Rotation         => "rotation",         Unit: Number, Building: Number;
+    X                => "x",                Unit: Number, Building: Number;
+    Y                => "y",                Unit: Number, Building: Number;
+    ShootX           => "shootX",           Unit: Number, Building: Number;
+    ShootY           => "shootY",           Unit: Number, Building: Number;
+    Size             => "size",             Unit: Number, Building: Number;
+    Dead             => "dead",             Unit: Bool, Building: Bool;
+    Range            => "range",            Unit: Number, Building: Number;
+    Shooting         => "shooting",         Unit: Bool, Building: Bool;
+    Boosting         => "boosting",         Unit: Bool;
+    MineX            => "mineX",            Unit: Number;
+    MineY            => "mineY",            Unit: Number;
+    Mining           => "mining",           Unit: Bool;
+    Speed            => "speed",            Unit: Number;
+    Team             => "team",             Unit: Number, Building: Number;
+    /// the content of the unit or of the building, like `@dagger` or `@router`
+    #[doc(alias = "type")]
+    ContentType      => "type",             Unit: UnitType, Building: Block;
+    Flag             => "flag",             Unit: Number;
+    /// `1` if controlled by a processor, `2` by a player and `3` by a command
+    Controlled       => "controlled",       Unit: Number, Building: Number;
+    /// the player or the unit commanding the unit, and the processor controlling the building
+    Controller       => "controller",       Unit: Unit, Building: Building;
+    PayloadCount     => "payloadCount",     Unit: Number, Building: Number;
+    PayloadType      => "payloadType",      Unit: Content, Building: Content;
+    Id               => "id",               Unit: Number;
+    Enabled          => "enabled",          Building: Bool;
+    Config           => "config",           Building: Content;
+    Color            => "color",            Building: Number;
+}
+
+/// the amount of the item held by the unit
+impl<U: Units> Property<Unit<U>> for Item {
+    type Output = Number;
+
+    fn name(self) -> String {
+        self.eval()
+    }
+}
+
+/// the amount of the item stored in the building
+impl<B: Buildings> Property<Building<B>> for Item {
+    type Output = Number;
+
+    fn name(self) -> String {
+        self.eval()
+    }
+}
+
+/// the amount of the liquid stored in the building
+impl<B: Buildings> Property<Building<B>> for Liquid {
+    type Output = Number;
+
+    fn name(self) -> String {
+        self.eval()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::Processor;
+
+    #[test]
+    fn sensor() {
+        Processor::scope(|core| {
+            let turret = core.from_mdt::<Building>("cyclone1");
+            let unit = core.unit();
+            let health = turret.sensor(Health) / turret.sensor(MaxHealth);
+            core.if_(unit.sensor(Dead), |_| {}).else_(|_| {
+                unit.r#move(turret.sensor(X), turret.sensor(Y));
+            });
+            unit.flag(health);
+            let _unused = unit.sensor(Shield);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "sensor v0 cyclone1 @health\n\
+                 sensor v1 cyclone1 @maxHealth\n\
+                 op div v0 v0 v1\n\
+                 sensor v1 @unit @dead\n\
+                 jump 8 notEqual v1 false\n\
+                 sensor v1 cyclone1 @x\n\
+                 sensor v2 cyclone1 @y\n\
+                 ucontrol move v1 v2 0 0 0\n\
+                 ucontrol flag v0 0 0 0 0\n"
+            );
+        });
+    }
+}
@@ -14,31 +14,31 @@ impl String {
     }
 }
 
-impl<S: ToString> Eval<String> for S {
+impl<S: ToString> Eval<'_, String> for S {
     default fn eval(self) -> String {
         String::Rc(self.to_string().into())
     }
 }
 
-impl Eval<String> for &'static str {
+impl Eval<'_, String> for &'static str {
     fn eval(self) -> String {
         String::Static(self)
     }
 }
 
-impl Eval<String> for std::string::String {
+impl Eval<'_, String> for std::string::String {
     fn eval(self) -> String {
         String::Rc(self.into())
     }
 }
 
-impl Eval<String> for Rc<std::string::String> {
+impl Eval<'_, String> for Rc<std::string::String> {
     fn eval(self) -> String {
         String::Rc(self)
     }
 }
 
-impl Eval<String> for std::borrow::Cow<'_, str> {
+impl Eval<'_, String> for std::borrow::Cow<'_, str> {
     fn eval(self) -> String {
         match self {
             std::borrow::Cow::Borrowed(s) => s.to_string(),
@@ -59,6 +59,20 @@ impl std::ops::Deref for String {
     }
 }
 
+impl PartialEq for String {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for String {}
+
+impl std::hash::Hash for String {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
 impl std::fmt::Display for String {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -0,0 +1,186 @@
+use super::{number::Number, Type};
+use crate::{command::op, eval::Eval, r#ref::Ref, String};
+
+/// a number used as a truth value, `0`/`null` are false
+#[derive(Eval)]
+pub enum Bool {
+    Immediate(bool),
+    Variable(String),
+}
+
+impl Type for Bool {
+    fn from_name(name: String) -> Self {
+        Self::Variable(name)
+    }
+}
+
+impl Eval<'_, String> for Bool {
+    fn eval(self) -> String {
+        match self {
+            Bool::Immediate(true) => String::Static("true"),
+            Bool::Immediate(false) => String::Static("false"),
+            Bool::Variable(s) => s,
+        }
+    }
+}
+
+impl Eval<'_, Number> for Bool {
+    fn eval(self) -> Number {
+        match self {
+            Bool::Immediate(b) => Number::Immediate(b.into()),
+            Bool::Variable(s) => Number::Variable(s),
+        }
+    }
+}
+
+impl Eval<'_, Bool> for bool {
+    fn eval(self) -> Bool {
+        Bool::Immediate(self)
+    }
+}
+
+macro_rules! comparisons_impl {
+    ($($(#[$attr:meta])* $method:ident($op:literal);)*) => {
+        impl<'a> Ref<'a, Number> {
+            $(
+            $(#[$attr])*
+            pub fn $method(self, rhs: impl Eval<'a, Number>) -> Ref<'a, Bool> {
+                self.binary_op($op, rhs).cast()
+            }
+            )*
+        }
+    };
+}
+
+comparisons_impl! {
+    #[doc(alias = "equal")]
+    eq("equal");
+    #[doc(alias = "notEqual")]
+    ne("notEqual");
+    #[doc(alias = "lessThan")]
+    lt("lessThan");
+    #[doc(alias = "lessThanEq")]
+    le("lessThanEq");
+    #[doc(alias = "greaterThan")]
+    gt("greaterThan");
+    #[doc(alias = "greaterThanEq")]
+    ge("greaterThanEq");
+    /// equal without type coercion
+    #[doc(alias = "strictEqual")]
+    strict_eq("strictEqual");
+    /// both are not `0`/`null`
+    land("land");
+}
+
+impl<'a> Ref<'a, Bool> {
+    fn push_op(self, op: &'static str, lhs: String, rhs: String) -> Self {
+        let result = self.core.new_unnamed();
+        let command = op::Op {
+            op: String::Static(op),
+            result: result.eval(),
+            lhs,
+            rhs,
+        };
+        self.core.borrow_mut().push_command(command);
+        result
+    }
+
+    fn logical_op(self, op: &'static str, rhs: impl Eval<'a, Bool>) -> Self {
+        self.push_op(op, self.eval(), rhs.eval().eval())
+    }
+
+    /// `value` as `0` or `1`, immediates already are
+    fn normalized(self, value: Bool) -> String {
+        match value {
+            Bool::Immediate(_) => value.eval(),
+            Bool::Variable(name) => self
+                .push_op("notEqual", name, String::Static("false"))
+                .eval(),
+        }
+    }
+
+    #[doc(alias = "land")]
+    pub fn and(self, rhs: impl Eval<'a, Bool>) -> Self {
+        self.logical_op("land", rhs)
+    }
+
+    /// `op or` is bitwise, `0.5 or 0` is `0`, so both sides are compared with `false` first
+    pub fn or(self, rhs: impl Eval<'a, Bool>) -> Self {
+        let lhs = self.normalized(Bool::Variable(self.eval()));
+        let rhs = self.normalized(rhs.eval());
+        self.push_op("or", lhs, rhs)
+    }
+}
+
+/// logical not, `op not` is bitwise so this compares with `false` instead
+impl std::ops::Not for Ref<'_, Bool> {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        self.logical_op("equal", false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::PassManager;
+    use crate::processor::Processor;
+
+    use super::*;
+
+    #[test]
+    fn compare() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new());
+            let a = core.from_mdt::<Number>("a");
+            let b = core.from_mdt::<Number>("b");
+
+            let lt = a.lt(b);
+            let both = lt.and(a.ne(0));
+            let _ = !both.or(true);
+            let _ = a + lt;
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "op lessThan v0 a b\n\
+                 op notEqual v1 a 0\n\
+                 op land v2 v0 v1\n\
+                 op notEqual v3 v2 false\n\
+                 op or v4 v3 true\n\
+                 op equal v5 v4 false\n\
+                 op add v6 a v0\n"
+            );
+        });
+    }
+
+    #[test]
+    fn or() {
+        Processor::scope(|core| {
+            let a = core.from_mdt::<Bool>("a");
+            let b = core.from_mdt::<Bool>("b");
+            let _ = core.if_(a.or(b), |_| {
+                core.unit().flag(1);
+            });
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "op notEqual v0 a false\n\
+                 op notEqual v1 b false\n\
+                 op or v0 v0 v1\n\
+                 jump 0 equal v0 false\n\
+                 ucontrol flag 1 0 0 0 0\n"
+            );
+        });
+
+        // `0.5 or false` is `0` in mlog but true here, so the `if` always runs
+        Processor::scope(|core| {
+            let half = core.from_mdt::<Number>("half").set_to(0.5).cast::<Bool>();
+            let either = half.or(false);
+            let _ = core.if_(either, |_| {
+                core.unit().flag(1);
+            });
+
+            assert_eq!(core.generate().unwrap(), "ucontrol flag 1 0 0 0 0\n");
+        });
+    }
+}
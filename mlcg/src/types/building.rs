@@ -1,4 +1,4 @@
-use super::{number::Number, Type};
+use super::{boolean::Bool, number::Number, Type};
 use crate::{
     abilities::{Shoot, Target},
     command,
@@ -23,48 +23,85 @@ impl<B: Buildings> Type for Building<B> {
     }
 }
 
-impl<B: Buildings> Eval<String> for Building<B> {
+impl<B: Buildings> Eval<'_, String> for Building<B> {
     fn eval(self) -> String {
         self.name
     }
 }
 
+impl<B: Buildings> Target for Building<B> {}
+
 pub trait Buildings {
-    fn class_name() -> &'static str;
+    /// the content of the building, like `@memory-cell`, `None` when it may be any building
+    fn class_name() -> Option<&'static str>;
 }
 
 pub struct AnyBuilding;
 
 impl Buildings for AnyBuilding {
-    fn class_name() -> &'static str {
-        panic!("unknow building")
+    fn class_name() -> Option<&'static str> {
+        None
+    }
+}
+
+impl Shoot for AnyBuilding {}
+
+/// buildings readable and writable with `read`/`write`
+pub trait Memory: Buildings {
+    /// number of slots
+    const CAPACITY: usize;
+}
+
+pub struct MemoryCell;
+
+impl Buildings for MemoryCell {
+    fn class_name() -> Option<&'static str> {
+        Some("@memory-cell")
     }
 }
 
-impl<'a, T> Ref<'a, T>
-where
-    T: Type + Shoot + Buildings,
-{
+impl Memory for MemoryCell {
+    const CAPACITY: usize = 64;
+}
+
+pub struct MemoryBank;
+
+impl Buildings for MemoryBank {
+    fn class_name() -> Option<&'static str> {
+        Some("@memory-bank")
+    }
+}
+
+impl Memory for MemoryBank {
+    const CAPACITY: usize = 512;
+}
+
+impl<'a, B: Buildings + Shoot> Ref<'a, Building<B>> {
     #[doc(alias = "target")]
-    pub fn shoot(&self, x: impl Eval<Number>, y: impl Eval<Number>, shoot: impl Eval<Number>) {
-        assert_same_core!(self, x, y, shoot);
-        let command = command::ucontrol::Target {
+    pub fn shoot(
+        &self,
+        x: impl Eval<'a, Number>,
+        y: impl Eval<'a, Number>,
+        shoot: impl Eval<'a, Bool>,
+    ) {
+        let command = command::control::Shoot {
+            of: self.eval(),
             x: x.eval().eval(),
             y: y.eval().eval(),
             shoot: shoot.eval().eval(),
         };
-        let command = command::ucontrol::Ucontrol::from(command);
+        let command = command::control::Control::from(command);
         self.core.borrow_mut().push_command(command)
     }
 
     #[doc(alias = "targetp")]
-    pub fn shootp<At: Target>(&self, at: impl Eval<At>, shoot: impl Eval<Number>) {
-        assert_same_core!(self, at, shoot);
-        let command = command::ucontrol::Targetp {
-            unit: at.eval().eval(),
+    pub fn shootp<At: Target>(&self, at: Ref<'a, At>, shoot: impl Eval<'a, Bool>) {
+        let command = command::control::Shootp {
+            of: self.eval(),
+            at: at.eval(),
             shoot: shoot.eval().eval(),
         };
-        let command = command::ucontrol::Ucontrol::from(command);
+        let command = command::control::Control::from(command);
         self.core.borrow_mut().push_command(command)
     }
 }
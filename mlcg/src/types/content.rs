@@ -0,0 +1,569 @@
+//! the contents of the game, typed so a misspelled `@coper` does not compile
+//!
+//! every content is an enum with a variant for each vanilla content, and [`Item::Variable`] and
+//! alike for variables holding one, like the results of [`crate::sensor`]
+//!
+//! ```compile_fail
+//! # use mlcg::{processor::Processor, types::building::Building};
+//! Processor::scope(|core| {
+//!     let core1 = core.from_mdt::<Building>("core1");
+//!     core.unit().item_take(core1, "@coper", 10);
+//! });
+//! ```
+
+use super::{boolean::Bool, number::Number, Type};
+use crate::{command::op, eval::Eval, r#ref::Ref, String};
+
+/// any content of the game, like `@copper`, `@water`, `@router` or `@dagger`
+#[derive(Eval, Debug, Clone)]
+pub struct Content {
+    name: String,
+}
+
+impl Type for Content {
+    fn from_name(name: String) -> Self {
+        Self { name }
+    }
+}
+
+impl Eval<'_, String> for Content {
+    fn eval(self) -> String {
+        self.name
+    }
+}
+
+/// no content, `null`, like the config of a block which takes none
+impl Eval<'_, Content> for () {
+    fn eval(self) -> Content {
+        Content {
+            name: String::Static("null"),
+        }
+    }
+}
+
+/// a number in place of a content, like the config of a block set by a number
+impl Eval<'_, Content> for Number {
+    fn eval(self) -> Content {
+        Content { name: self.eval() }
+    }
+}
+
+/// `op` comparing two contents, with a new variable as result
+fn compare<'a, T: Type>(lhs: Ref<'a, T>, op: &'static str, rhs: String) -> Ref<'a, Bool> {
+    let result = lhs.core.new_unnamed();
+    let command = op::Op {
+        op: String::Static(op),
+        result: result.eval(),
+        lhs: lhs.eval(),
+        rhs,
+    };
+    lhs.core.borrow_mut().push_command(command);
+    result
+}
+
+macro_rules! comparisons_impl {
+    ($($content:ident),*) => {$(
+        impl<'a> Ref<'a, $content> {
+            #[doc(alias = "equal")]
+            pub fn eq(self, rhs: impl Eval<'a, $content>) -> Ref<'a, Bool> {
+                compare(self, "equal", rhs.eval().eval())
+            }
+
+            #[doc(alias = "notEqual")]
+            pub fn ne(self, rhs: impl Eval<'a, $content>) -> Ref<'a, Bool> {
+                compare(self, "notEqual", rhs.eval().eval())
+            }
+        }
+    )*};
+}
+
+comparisons_impl!(Content, Item, Liquid, UnitType, Block);
+
+macro_rules! contents {
+    ($(
+        $(#[$attr:meta])*
+        $content:ident {
+            $( $variant:ident => $name:literal, )*
+        }
+    )*) => {$(
+        $(#[$attr])*
+        #[derive(Eval, Debug, Clone, PartialEq, Eq)]
+        pub enum $content {
+            $(
+            #[doc = concat!("`@", $name, "`")]
+            $variant,
+            )*
+            /// a variable holding the content
+            Variable(String),
+        }
+
+        impl Type for $content {
+            fn from_name(name: String) -> Self {
+                Self::Variable(name)
+            }
+        }
+
+        impl Eval<'_, String> for $content {
+            fn eval(self) -> String {
+                match self {
+                    $( Self::$variant => String::Static(concat!('@', $name)), )*
+                    Self::Variable(name) => name,
+                }
+            }
+        }
+
+        impl Eval<'_, Content> for $content {
+            fn eval(self) -> Content {
+                Content { name: self.eval() }
+            }
+        }
+    )*};
+}
+
+contents! {
+    /// an item, like `@copper`
+    Item {
+        Copper        => "copper",
+        Lead          => "lead",
+        Metaglass     => "metaglass",
+        Graphite      => "graphite",
+        Sand          => "sand",
+        Coal          => "coal",
+        Titanium      => "titanium",
+        Thorium       => "thorium",
+        Scrap         => "scrap",
+        Silicon       => "silicon",
+        Plastanium    => "plastanium",
+        PhaseFabric   => "phase-fabric",
+        SurgeAlloy    => "surge-alloy",
+        SporePod      => "spore-pod",
+        BlastCompound => "blast-compound",
+        Pyratite      => "pyratite",
+        Beryllium     => "beryllium",
+        Tungsten      => "tungsten",
+        Oxide         => "oxide",
+        Carbide       => "carbide",
+        FissileMatter => "fissile-matter",
+        DormantCyst   => "dormant-cyst",
+    }
+
+    /// a liquid, like `@water`
+    Liquid {
+        Water     => "water",
+        Slag      => "slag",
+        Oil       => "oil",
+        Cryofluid => "cryofluid",
+        Neoplasm  => "neoplasm",
+        Arkycite  => "arkycite",
+        Gallium   => "gallium",
+        Ozone     => "ozone",
+        Hydrogen  => "hydrogen",
+        Nitrogen  => "nitrogen",
+        Cyanogen  => "cyanogen",
+    }
+
+    /// the content of a unit, like `@dagger`
+    UnitType {
+        Dagger   => "dagger",
+        Mace     => "mace",
+        Fortress => "fortress",
+        Scepter  => "scepter",
+        Reign    => "reign",
+        Nova     => "nova",
+        Pulsar   => "pulsar",
+        Quasar   => "quasar",
+        Vela     => "vela",
+        Corvus   => "corvus",
+        Crawler  => "crawler",
+        Atrax    => "atrax",
+        Spiroct  => "spiroct",
+        Arkyid   => "arkyid",
+        Toxopid  => "toxopid",
+        Flare    => "flare",
+        Horizon  => "horizon",
+        Zenith   => "zenith",
+        Antumbra => "antumbra",
+        Eclipse  => "eclipse",
+        Mono     => "mono",
+        Poly     => "poly",
+        Mega     => "mega",
+        Quad     => "quad",
+        Oct      => "oct",
+        Risso    => "risso",
+        Minke    => "minke",
+        Bryde    => "bryde",
+        Sei      => "sei",
+        Omura    => "omura",
+        Retusa   => "retusa",
+        Oxynoe   => "oxynoe",
+        Cyerce   => "cyerce",
+        Aegires  => "aegires",
+        Navanax  => "navanax",
+        Alpha    => "alpha",
+        Beta     => "beta",
+        Gamma    => "gamma",
+        Stell    => "stell",
+        Locus    => "locus",
+        Precept  => "precept",
+        Vanquish => "vanquish",
+        Conquer  => "conquer",
+        Merui    => "merui",
+        Cleroi   => "cleroi",
+        Anthicus => "anthicus",
+        Tecta    => "tecta",
+        Collaris => "collaris",
+        Elude    => "elude",
+        Avert    => "avert",
+        Obviate  => "obviate",
+        Quell    => "quell",
+        Disrupt  => "disrupt",
+        Evoke    => "evoke",
+        Incite   => "incite",
+        Emanate  => "emanate",
+    }
+
+    /// the content of a building, or a floor, like `@router` or `@ore-copper`
+    Block {
+        // environment
+        Air                         => "air",
+        Spawn                       => "spawn",
+        Stone                       => "stone",
+        SandFloor                   => "sand-floor",
+        Darksand                    => "darksand",
+        Grass                       => "grass",
+        Dirt                        => "dirt",
+        Ice                         => "ice",
+        Snow                        => "snow",
+        ShallowWater                => "shallow-water",
+        DeepWater                   => "deep-water",
+        TaintedWater                => "tainted-water",
+        SandWater                   => "sand-water",
+        DarksandWater               => "darksand-water",
+        MetalFloor                  => "metal-floor",
+        Space                       => "space",
+        OreCopper                   => "ore-copper",
+        OreLead                     => "ore-lead",
+        OreScrap                    => "ore-scrap",
+        OreCoal                     => "ore-coal",
+        OreTitanium                 => "ore-titanium",
+        OreThorium                  => "ore-thorium",
+        OreBeryllium                => "ore-beryllium",
+        OreTungsten                 => "ore-tungsten",
+
+        // crafting
+        GraphitePress               => "graphite-press",
+        MultiPress                  => "multi-press",
+        SiliconSmelter              => "silicon-smelter",
+        SiliconCrucible             => "silicon-crucible",
+        Kiln                        => "kiln",
+        PlastaniumCompressor        => "plastanium-compressor",
+        PhaseWeaver                 => "phase-weaver",
+        SurgeSmelter                => "surge-smelter",
+        CryofluidMixer              => "cryofluid-mixer",
+        PyratiteMixer               => "pyratite-mixer",
+        BlastMixer                  => "blast-mixer",
+        Melter                      => "melter",
+        Separator                   => "separator",
+        Disassembler                => "disassembler",
+        SporePress                  => "spore-press",
+        Pulverizer                  => "pulverizer",
+        CoalCentrifuge              => "coal-centrifuge",
+        Incinerator                 => "incinerator",
+        SiliconArcFurnace           => "silicon-arc-furnace",
+        Electrolyzer                => "electrolyzer",
+        AtmosphericConcentrator     => "atmospheric-concentrator",
+        OxidationChamber            => "oxidation-chamber",
+        ElectricHeater              => "electric-heater",
+        SlagHeater                  => "slag-heater",
+        PhaseHeater                 => "phase-heater",
+        HeatRedirector              => "heat-redirector",
+        HeatRouter                  => "heat-router",
+        SlagIncinerator             => "slag-incinerator",
+        CarbideCrucible             => "carbide-crucible",
+        SlagCentrifuge              => "slag-centrifuge",
+        SurgeCrucible               => "surge-crucible",
+        CyanogenSynthesizer         => "cyanogen-synthesizer",
+        PhaseSynthesizer            => "phase-synthesizer",
+        HeatReactor                 => "heat-reactor",
+
+        // sandbox
+        PowerSource                 => "power-source",
+        PowerVoid                   => "power-void",
+        ItemSource                  => "item-source",
+        ItemVoid                    => "item-void",
+        LiquidSource                => "liquid-source",
+        LiquidVoid                  => "liquid-void",
+        PayloadSource               => "payload-source",
+        PayloadVoid                 => "payload-void",
+        Illuminator                 => "illuminator",
+
+        // walls
+        CopperWall                  => "copper-wall",
+        CopperWallLarge             => "copper-wall-large",
+        TitaniumWall                => "titanium-wall",
+        TitaniumWallLarge           => "titanium-wall-large",
+        PlastaniumWall              => "plastanium-wall",
+        PlastaniumWallLarge         => "plastanium-wall-large",
+        ThoriumWall                 => "thorium-wall",
+        ThoriumWallLarge            => "thorium-wall-large",
+        PhaseWall                   => "phase-wall",
+        PhaseWallLarge              => "phase-wall-large",
+        SurgeWall                   => "surge-wall",
+        SurgeWallLarge              => "surge-wall-large",
+        Door                        => "door",
+        DoorLarge                   => "door-large",
+        ScrapWall                   => "scrap-wall",
+        ScrapWallLarge              => "scrap-wall-large",
+        ScrapWallHuge               => "scrap-wall-huge",
+        ScrapWallGigantic           => "scrap-wall-gigantic",
+        Thruster                    => "thruster",
+        BerylliumWall               => "beryllium-wall",
+        BerylliumWallLarge          => "beryllium-wall-large",
+        TungstenWall                => "tungsten-wall",
+        TungstenWallLarge           => "tungsten-wall-large",
+        BlastDoor                   => "blast-door",
+        ReinforcedSurgeWall         => "reinforced-surge-wall",
+        ReinforcedSurgeWallLarge    => "reinforced-surge-wall-large",
+        CarbideWall                 => "carbide-wall",
+        CarbideWallLarge            => "carbide-wall-large",
+        ShieldedWall                => "shielded-wall",
+
+        // defense
+        Mender                      => "mender",
+        MendProjector               => "mend-projector",
+        OverdriveProjector          => "overdrive-projector",
+        OverdriveDome               => "overdrive-dome",
+        ForceProjector              => "force-projector",
+        ShockMine                   => "shock-mine",
+        Radar                       => "radar",
+        BuildTower                  => "build-tower",
+        RegenProjector              => "regen-projector",
+        ShockwaveTower              => "shockwave-tower",
+        ShieldProjector             => "shield-projector",
+        LargeShieldProjector        => "large-shield-projector",
+
+        // transport
+        Conveyor                    => "conveyor",
+        TitaniumConveyor            => "titanium-conveyor",
+        PlastaniumConveyor          => "plastanium-conveyor",
+        ArmoredConveyor             => "armored-conveyor",
+        Junction                    => "junction",
+        BridgeConveyor              => "bridge-conveyor",
+        PhaseConveyor               => "phase-conveyor",
+        Sorter                      => "sorter",
+        InvertedSorter              => "inverted-sorter",
+        Router                      => "router",
+        Distributor                 => "distributor",
+        OverflowGate                => "overflow-gate",
+        UnderflowGate               => "underflow-gate",
+        MassDriver                  => "mass-driver",
+        Duct                        => "duct",
+        ArmoredDuct                 => "armored-duct",
+        DuctRouter                  => "duct-router",
+        OverflowDuct                => "overflow-duct",
+        UnderflowDuct               => "underflow-duct",
+        DuctBridge                  => "duct-bridge",
+        DuctUnloader                => "duct-unloader",
+        SurgeConveyor               => "surge-conveyor",
+        SurgeRouter                 => "surge-router",
+        UnitCargoLoader             => "unit-cargo-loader",
+        UnitCargoUnloadPoint        => "unit-cargo-unload-point",
+
+        // liquid
+        MechanicalPump              => "mechanical-pump",
+        RotaryPump                  => "rotary-pump",
+        ImpulsePump                 => "impulse-pump",
+        Conduit                     => "conduit",
+        PulseConduit                => "pulse-conduit",
+        PlatedConduit               => "plated-conduit",
+        LiquidRouter                => "liquid-router",
+        LiquidContainer             => "liquid-container",
+        LiquidTank                  => "liquid-tank",
+        LiquidJunction              => "liquid-junction",
+        BridgeConduit               => "bridge-conduit",
+        PhaseConduit                => "phase-conduit",
+        ReinforcedPump              => "reinforced-pump",
+        ReinforcedConduit           => "reinforced-conduit",
+        ReinforcedLiquidJunction    => "reinforced-liquid-junction",
+        ReinforcedBridgeConduit     => "reinforced-bridge-conduit",
+        ReinforcedLiquidRouter      => "reinforced-liquid-router",
+        ReinforcedLiquidContainer   => "reinforced-liquid-container",
+        ReinforcedLiquidTank        => "reinforced-liquid-tank",
+
+        // power
+        CombustionGenerator         => "combustion-generator",
+        PowerNode                   => "power-node",
+        PowerNodeLarge              => "power-node-large",
+        SurgeTower                  => "surge-tower",
+        Diode                       => "diode",
+        Battery                     => "battery",
+        BatteryLarge                => "battery-large",
+        ThermalGenerator            => "thermal-generator",
+        SteamGenerator              => "steam-generator",
+        DifferentialGenerator       => "differential-generator",
+        RtgGenerator                => "rtg-generator",
+        SolarPanel                  => "solar-panel",
+        LargeSolarPanel             => "large-solar-panel",
+        ThoriumReactor              => "thorium-reactor",
+        ImpactReactor               => "impact-reactor",
+        BeamNode                    => "beam-node",
+        BeamTower                   => "beam-tower",
+        BeamLink                    => "beam-link",
+        TurbineCondenser            => "turbine-condenser",
+        ChemicalCombustionChamber   => "chemical-combustion-chamber",
+        PyrolysisGenerator          => "pyrolysis-generator",
+        FluxReactor                 => "flux-reactor",
+        NeoplasiaReactor            => "neoplasia-reactor",
+
+        // production
+        MechanicalDrill             => "mechanical-drill",
+        PneumaticDrill              => "pneumatic-drill",
+        LaserDrill                  => "laser-drill",
+        BlastDrill                  => "blast-drill",
+        WaterExtractor              => "water-extractor",
+        Cultivator                  => "cultivator",
+        OilExtractor                => "oil-extractor",
+        VentCondenser               => "vent-condenser",
+        CliffCrusher                => "cliff-crusher",
+        LargeCliffCrusher           => "large-cliff-crusher",
+        PlasmaBore                  => "plasma-bore",
+        LargePlasmaBore             => "large-plasma-bore",
+        ImpactDrill                 => "impact-drill",
+        EruptionDrill               => "eruption-drill",
+
+        // storage
+        CoreShard                   => "core-shard",
+        CoreFoundation              => "core-foundation",
+        CoreNucleus                 => "core-nucleus",
+        CoreBastion                 => "core-bastion",
+        CoreCitadel                 => "core-citadel",
+        CoreAcropolis               => "core-acropolis",
+        Container                   => "container",
+        Vault                       => "vault",
+        Unloader                    => "unloader",
+        ReinforcedContainer         => "reinforced-container",
+        ReinforcedVault             => "reinforced-vault",
+
+        // turrets
+        Duo                         => "duo",
+        Scatter                     => "scatter",
+        Scorch                      => "scorch",
+        Hail                        => "hail",
+        Wave                        => "wave",
+        Lancer                      => "lancer",
+        Arc                         => "arc",
+        Parallax                    => "parallax",
+        Swarmer                     => "swarmer",
+        Salvo                       => "salvo",
+        Segment                     => "segment",
+        Tsunami                     => "tsunami",
+        Fuse                        => "fuse",
+        Ripple                      => "ripple",
+        Cyclone                     => "cyclone",
+        Foreshadow                  => "foreshadow",
+        Spectre                     => "spectre",
+        Meltdown                    => "meltdown",
+        Breach                      => "breach",
+        Diffuse                     => "diffuse",
+        Sublimate                   => "sublimate",
+        Titan                       => "titan",
+        Disperse                    => "disperse",
+        Afflict                     => "afflict",
+        Lustre                      => "lustre",
+        Scathe                      => "scathe",
+        Smite                       => "smite",
+        Malign                      => "malign",
+
+        // units
+        GroundFactory               => "ground-factory",
+        AirFactory                  => "air-factory",
+        NavalFactory                => "naval-factory",
+        AdditiveReconstructor       => "additive-reconstructor",
+        MultiplicativeReconstructor => "multiplicative-reconstructor",
+        ExponentialReconstructor    => "exponential-reconstructor",
+        TetrativeReconstructor      => "tetrative-reconstructor",
+        RepairPoint                 => "repair-point",
+        RepairTurret                => "repair-turret",
+        TankFabricator              => "tank-fabricator",
+        ShipFabricator              => "ship-fabricator",
+        MechFabricator              => "mech-fabricator",
+        TankRefabricator            => "tank-refabricator",
+        ShipRefabricator            => "ship-refabricator",
+        MechRefabricator            => "mech-refabricator",
+        PrimeRefabricator           => "prime-refabricator",
+        TankAssembler               => "tank-assembler",
+        ShipAssembler               => "ship-assembler",
+        MechAssembler               => "mech-assembler",
+        BasicAssemblerModule        => "basic-assembler-module",
+        UnitRepairTower             => "unit-repair-tower",
+
+        // payload
+        PayloadConveyor             => "payload-conveyor",
+        PayloadRouter               => "payload-router",
+        ReinforcedPayloadConveyor   => "reinforced-payload-conveyor",
+        ReinforcedPayloadRouter     => "reinforced-payload-router",
+        PayloadMassDriver           => "payload-mass-driver",
+        LargePayloadMassDriver      => "large-payload-mass-driver",
+        SmallDeconstructor          => "small-deconstructor",
+        Deconstructor               => "deconstructor",
+        Constructor                 => "constructor",
+        LargeConstructor            => "large-constructor",
+        PayloadLoader               => "payload-loader",
+        PayloadUnloader             => "payload-unloader",
+
+        // logic
+        Message                     => "message",
+        Switch                      => "switch",
+        MicroProcessor              => "micro-processor",
+        LogicProcessor              => "logic-processor",
+        HyperProcessor              => "hyper-processor",
+        MemoryCell                  => "memory-cell",
+        MemoryBank                  => "memory-bank",
+        LogicDisplay                => "logic-display",
+        LargeLogicDisplay           => "large-logic-display",
+        Canvas                      => "canvas",
+        ReinforcedMessage           => "reinforced-message",
+        WorldProcessor              => "world-processor",
+        WorldCell                   => "world-cell",
+        WorldMessage                => "world-message",
+
+        // campaign
+        LaunchPad                   => "launch-pad",
+        InterplanetaryAccelerator   => "interplanetary-accelerator",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        processor::Processor,
+        sensor::{FirstItem, Health},
+        types::building::Building,
+    };
+
+    #[test]
+    fn contents() {
+        Processor::scope(|core| {
+            let core1 = core.from_mdt::<Building>("core1");
+            let unit = core.unit();
+            let copper = unit.sensor(FirstItem).eq(Item::Copper);
+            let low = core1.sensor(Item::SurgeAlloy).lt(100);
+            let _ = core.if_(copper.and(low), |_| {
+                unit.item_take(core1, Item::SurgeAlloy, unit.sensor(Health));
+            });
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "sensor v0 @unit @firstItem\n\
+                 op equal v0 v0 @copper\n\
+                 sensor v1 core1 @surge-alloy\n\
+                 op lessThan v1 v1 100\n\
+                 op land v0 v0 v1\n\
+                 jump 0 equal v0 false\n\
+                 sensor v0 @unit @health\n\
+                 ucontrol itemTake core1 @surge-alloy v0 0 0\n"
+            );
+        });
+    }
+}
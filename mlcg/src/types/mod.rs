@@ -1,11 +1,13 @@
 use crate::eval::Eval;
 use crate::String;
 
+pub mod boolean;
 pub mod building;
 pub mod config;
+pub mod content;
 pub mod number;
 pub mod unit;
 
-pub trait Type: Eval<String> {
+pub trait Type: for<'id> Eval<'id, String> {
     fn from_name(name: crate::String) -> Self;
 }
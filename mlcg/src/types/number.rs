@@ -1,5 +1,5 @@
 use super::Type;
-use crate::{eval::Eval, r#ref::Ref, String};
+use crate::{command::op, eval::Eval, r#ref::Ref, String};
 
 #[derive(Eval)]
 pub enum Number {
@@ -13,7 +13,7 @@ impl Type for Number {
     }
 }
 
-impl Eval<String> for Number {
+impl Eval<'_, String> for Number {
     fn eval(self) -> String {
         match self {
             Number::Immediate(f) => String::new(f),
@@ -22,7 +22,7 @@ impl Eval<String> for Number {
     }
 }
 
-impl<F> Eval<Number> for F
+impl<F> Eval<'_, Number> for F
 where
     f64: From<F>,
 {
@@ -31,58 +31,73 @@ where
     }
 }
 
+impl<'a> Ref<'a, Number> {
+    /// `op` with a new variable as result
+    pub(crate) fn binary_op(self, op: &'static str, rhs: impl Eval<'a, Number>) -> Self {
+        let result = self.core.new_unnamed();
+        let command = op::Op {
+            op: String::Static(op),
+            result: result.eval(),
+            lhs: self.eval(),
+            rhs: rhs.eval().eval(),
+        };
+        self.core.borrow_mut().push_command(command);
+        result
+    }
+
+    /// `op` with `self` as result
+    pub(crate) fn binary_op_assign(self, op: &'static str, rhs: impl Eval<'a, Number>) {
+        let command = op::Op {
+            op: String::Static(op),
+            result: self.eval(),
+            lhs: self.eval(),
+            rhs: rhs.eval().eval(),
+        };
+        self.core.borrow_mut().push_command(command);
+    }
+
+    /// `op` with a new variable as result and `self` as second operand
+    pub(crate) fn reversed_binary_op(self, op: &'static str, lhs: impl Eval<'a, Number>) -> Self {
+        let result = self.core.new_unnamed();
+        let command = op::Op {
+            op: String::Static(op),
+            result: result.eval(),
+            lhs: lhs.eval().eval(),
+            rhs: self.eval(),
+        };
+        self.core.borrow_mut().push_command(command);
+        result
+    }
+
+    /// `op` which ignores its second operand
+    pub(crate) fn unary_op(self, op: &'static str) -> Self {
+        self.binary_op(op, 0)
+    }
+}
+
 macro_rules! binary_ops_impl {
-    ($($trait:ident => $method:ident,)*) => {
+    ($($trait:ident => $method:ident($op:literal),)*) => {
         $(
-        impl<N> std::ops::$trait<N> for Ref<'_, Number>
+        impl<'a, N> std::ops::$trait<N> for Ref<'a, Number>
         where
-            N: Eval<Number>,
+            N: Eval<'a, Number>,
         {
             type Output = Self;
 
             fn $method(self, rhs: N) -> Self::Output {
-                use crate::command::*;
-                let processor = self.core;
-                let result = processor.new_unnamed();
-                let lhs: Number = self.eval();
-                let rhs: Number = rhs.eval();
-                {
-                    let lhs: String = lhs.eval();
-                    let rhs: String = rhs.eval();
-                    let result = result.eval();
-                    processor.borrow_mut().push_command(op::Op {
-                        op: stringify!($method).eval(),
-                        result,
-                        lhs,
-                        rhs,
-                    });
-                }
-                result
+                self.binary_op($op, rhs)
             }
         }
         )*
     };
-    ($($trait:ident($op:ident) => $method:ident($op_method:ident),)*) => {
+    ($($trait:ident($base:ident) => $method:ident($op:literal),)*) => {
         $(
-        impl<N> std::ops::$trait<N> for Ref<'_, Number>
+        impl<'a, N> std::ops::$trait<N> for Ref<'a, Number>
         where
-            N: Eval<Number>,
+            N: Eval<'a, Number>,
         {
-            fn $method(&mut self, rhs: N){
-                use crate::command::*;
-                let processor = self.core;
-
-                let lhs: Number = self.eval();
-                let lhs: String = lhs.eval();
-                let rhs: Number = rhs.eval();
-
-                let mut processor = processor.borrow_mut();
-                processor.push_command(op::Op {
-                    op: stringify!($op_method).eval(),
-                    result: lhs.clone(),
-                    lhs: lhs.eval(),
-                    rhs: rhs.eval(),
-                });
+            fn $method(&mut self, rhs: N) {
+                self.binary_op_assign($op, rhs)
             }
         }
         )*
@@ -90,50 +105,254 @@ macro_rules! binary_ops_impl {
 }
 
 binary_ops_impl! {
-    Add => add,
-    Sub => sub,
-    Mul => mul,
-    Div => div,
-    Rem => rem,
+    Add => add("add"),
+    Sub => sub("sub"),
+    Mul => mul("mul"),
+    Div => div("div"),
+    Rem => rem("mod"),
+    BitAnd => bitand("and"),
+    BitOr => bitor("or"),
+    BitXor => bitxor("xor"),
+    Shl => shl("shl"),
+    Shr => shr("shr"),
 }
 
 binary_ops_impl! {
-    AddAssign(Add) => add_assign(add),
-    SubAssign(Sub) => sub_assign(sub),
-    MulAssign(Mul) => mul_assign(mul),
-    DivAssign(Div) => div_assign(div),
-    RemAssign(Rem) => rem_assign(rem),
+    AddAssign(Add) => add_assign("add"),
+    SubAssign(Sub) => sub_assign("sub"),
+    MulAssign(Mul) => mul_assign("mul"),
+    DivAssign(Div) => div_assign("div"),
+    RemAssign(Rem) => rem_assign("mod"),
+    BitAndAssign(BitAnd) => bitand_assign("and"),
+    BitOrAssign(BitOr) => bitor_assign("or"),
+    BitXorAssign(BitXor) => bitxor_assign("xor"),
+    ShlAssign(Shl) => shl_assign("shl"),
+    ShrAssign(Shr) => shr_assign("shr"),
+}
+
+/// operators with an immediate as first operand
+macro_rules! reversed_ops_impl {
+    ($($ty:ty),*) => {
+        $(
+        reversed_ops_impl! {
+            $ty:
+            Add => add("add"),
+            Sub => sub("sub"),
+            Mul => mul("mul"),
+            Div => div("div"),
+            Rem => rem("mod"),
+            BitAnd => bitand("and"),
+            BitOr => bitor("or"),
+            BitXor => bitxor("xor"),
+            Shl => shl("shl"),
+            Shr => shr("shr"),
+        }
+        )*
+    };
+    ($ty:ty: $($trait:ident => $method:ident($op:literal),)*) => {
+        $(
+        impl<'a> std::ops::$trait<Ref<'a, Number>> for $ty {
+            type Output = Ref<'a, Number>;
+
+            fn $method(self, rhs: Ref<'a, Number>) -> Self::Output {
+                rhs.reversed_binary_op($op, self)
+            }
+        }
+        )*
+    };
+}
+
+reversed_ops_impl!(f64, f32, i32, u32, i16, u16, i8, u8, bool);
+
+impl std::ops::Neg for Ref<'_, Number> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self.reversed_binary_op("sub", 0)
+    }
+}
+
+/// bitwise not
+impl std::ops::Not for Ref<'_, Number> {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        self.unary_op("not")
+    }
+}
+
+macro_rules! named_ops_impl {
+    ($($(#[$attr:meta])* $method:ident($op:literal);)*) => {
+        impl<'a> Ref<'a, Number> {
+            $(
+            $(#[$attr])*
+            pub fn $method(self, rhs: impl Eval<'a, Number>) -> Self {
+                self.binary_op($op, rhs)
+            }
+            )*
+        }
+    };
+    ($($(#[$attr:meta])* $method:ident($op:literal) unary;)*) => {
+        impl<'a> Ref<'a, Number> {
+            $(
+            $(#[$attr])*
+            pub fn $method(self) -> Self {
+                self.unary_op($op)
+            }
+            )*
+        }
+    };
+}
+
+named_ops_impl! {
+    /// integer division
+    idiv("idiv");
+    /// modulo, with the sign of `self`
+    #[doc(alias = "%")]
+    r#mod("mod");
+    /// modulo, with the sign of `rhs`
+    emod("emod");
+    pow("pow");
+    /// bitwise or
+    or("or");
+    /// bitwise and
+    and("and");
+    /// bitwise xor
+    xor("xor");
+    /// shift left, the same as `self << rhs`
+    #[doc(alias = "<<")]
+    #[allow(clippy::should_implement_trait)]
+    shl("shl");
+    /// shift right, the same as `self >> rhs`
+    #[doc(alias = ">>")]
+    #[allow(clippy::should_implement_trait)]
+    shr("shr");
+    max("max");
+    min("min");
+    /// angle of the vector `(self, rhs)` in degrees
+    angle("angle");
+    /// absolute distance between the angles `self` and `rhs` in degrees
+    angle_diff("angleDiff");
+    /// length of the vector `(self, rhs)`
+    len("len");
+    /// 2D simplex noise at `(self, rhs)`
+    noise("noise");
+}
+
+named_ops_impl! {
+    /// bitwise not, the same as `!self`
+    #[doc(alias = "!")]
+    #[allow(clippy::should_implement_trait)]
+    not("not") unary;
+    abs("abs") unary;
+    /// natural logarithm
+    log("log") unary;
+    log10("log10") unary;
+    floor("floor") unary;
+    ceil("ceil") unary;
+    sqrt("sqrt") unary;
+    /// random decimal in `[0, self)`
+    rand("rand") unary;
+    /// sine, in degrees
+    sin("sin") unary;
+    /// cosine, in degrees
+    cos("cos") unary;
+    /// tangent, in degrees
+    tan("tan") unary;
+    /// arc sine, in degrees
+    asin("asin") unary;
+    /// arc cosine, in degrees
+    acos("acos") unary;
+    /// arc tangent, in degrees
+    atan("atan") unary;
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::ir::PassManager;
     use crate::processor::Processor;
 
     use super::*;
 
     #[test]
     fn operator() {
-        let core = Processor::default();
-        let a = core.from_mdt::<Number>("a");
-        let b = core.from_mdt::<Number>("b");
+        Processor::scope(|core| {
+            let a = core.from_mdt::<Number>("a");
+            let b = core.from_mdt::<Number>("b");
 
-        let c = a + b;
-        let d = c + 114514;
-        let e = d + false;
-        let _f = e + 1919.810;
+            let c = a + b;
+            let d = c + 114514;
+            let e = d + false;
+            let _f = e + 1919.810;
 
-        core.write_to_stdout();
+            core.write_to_stdout();
+        });
     }
 
     #[test]
     fn operator_assign() {
-        let core = Processor::default();
-        let mut a = core.from_mdt("a");
-        let mut b = core.from_mdt("b");
-        a += b;
-        b += a;
-        let _c = a + b;
-
-        core.write_to_stdout();
+        Processor::scope(|core| {
+            let mut a = core.from_mdt("a");
+            let mut b = core.from_mdt("b");
+            a += b;
+            b += a;
+            let _c = a + b;
+
+            core.write_to_stdout();
+        });
+    }
+
+    #[test]
+    fn ops() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new());
+            let a = core.from_mdt::<Number>("a");
+            let b = core.from_mdt::<Number>("b");
+
+            let _ = a % b;
+            let _ = -a;
+            let _ = !a;
+            let _ = (a << 2) ^ b;
+            let _ = a.r#mod(b).angle_diff(90);
+            let _ = a.sqrt().le(b);
+            let _ = a.not().shl(b).shr(1);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "op mod v0 a b\n\
+                 op sub v1 0 a\n\
+                 op not v2 a 0\n\
+                 op shl v3 a 2\n\
+                 op xor v4 v3 b\n\
+                 op mod v5 a b\n\
+                 op angleDiff v6 v5 90\n\
+                 op sqrt v7 a 0\n\
+                 op lessThanEq v8 v7 b\n\
+                 op not v9 a 0\n\
+                 op shl v10 v9 b\n\
+                 op shr v11 v10 1\n"
+            );
+        });
+    }
+
+    #[test]
+    fn reversed_operator() {
+        Processor::scope(|core| {
+            core.set_passes(PassManager::new());
+            let a = core.from_mdt::<Number>("a");
+
+            let _ = 2.0 * a;
+            let _ = 100 - a;
+            let _ = 1u8 << a;
+            let _ = true ^ a;
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "op mul v0 2 a\n\
+                 op sub v1 100 a\n\
+                 op shl v2 1 a\n\
+                 op xor v3 1 a\n"
+            );
+        });
     }
 }
@@ -1,4 +1,6 @@
+use super::boolean::Bool;
 use super::building::{Building, Buildings};
+use super::content::{Block, Content, Item};
 use super::number::Number;
 use super::Type;
 use crate::abilities::*;
@@ -31,7 +33,7 @@ impl<U: Units> Type for Unit<U> {
     }
 }
 
-impl<U: Units> Eval<String> for Unit<U> {
+impl<U: Units> Eval<'_, String> for Unit<U> {
     fn eval(self) -> String {
         self.name
     }
@@ -43,8 +45,8 @@ pub trait Units {
     fn class_name() -> &'static str;
 }
 
-impl Processor {
-    pub fn unit_bind<U: Units>(&self) -> Ref<'_, Unit> {
+impl<'id> Processor<'id> {
+    pub fn unit_bind<U: Units>(&'id self) -> Ref<'id, Unit> {
         self.borrow_mut()
             .push_command(crate::command::ubind::Ubind {
                 ty: U::class_name().eval(),
@@ -52,12 +54,16 @@ impl Processor {
         self.unit()
     }
 
-    pub fn bind<U: Units>(&self, unit: Ref<'_, Unit<U>>) -> Ref<'_, Unit> {
+    pub fn bind<U: Units>(&'id self, unit: Ref<'id, Unit<U>>) -> Ref<'id, Unit> {
         self.unit().set_to(unit.cast::<Unit>())
     }
 }
 
 impl<'a, U: Units> Ref<'a, Unit<U>> {
+    fn ucontrol(&self, command: impl Into<ucontrol::Ucontrol>) {
+        self.core.borrow_mut().push_command(command.into());
+    }
+
     pub fn bind(&self) -> Ref<'a, Unit> {
         self.core.bind(*self)
     }
@@ -69,16 +75,16 @@ impl<'a, U: Units> Ref<'a, Unit<U>> {
 }
 
 impl<'a> Ref<'a, Unit> {
-    fn ucontrol(&self, command: impl Into<ucontrol::Ucontrol>) {
-        self.core.borrow_mut().push_command(command.into());
-    }
-
     pub fn idle(&self) {
         self.ucontrol(ucontrol::Idle {});
     }
 
-    pub fn r#move(&self, x: impl Eval<Number>, y: impl Eval<Number>) {
-        assert_same_core!(self, x, y);
+    /// stop moving, mining and building
+    pub fn stop(&self) {
+        self.ucontrol(ucontrol::Stop {});
+    }
+
+    pub fn r#move(&self, x: impl Eval<'a, Number>, y: impl Eval<'a, Number>) {
         let command = ucontrol::Move {
             x: x.eval().eval(),
             y: y.eval().eval(),
@@ -86,8 +92,12 @@ impl<'a> Ref<'a, Unit> {
         self.ucontrol(command);
     }
 
-    pub fn approach(&self, x: impl Eval<Number>, y: impl Eval<Number>, radius: impl Eval<Number>) {
-        assert_same_core!(self, x, y, radius);
+    pub fn approach(
+        &self,
+        x: impl Eval<'a, Number>,
+        y: impl Eval<'a, Number>,
+        radius: impl Eval<'a, Number>,
+    ) {
         let command = ucontrol::Approach {
             x: x.eval().eval(),
             y: y.eval().eval(),
@@ -96,8 +106,7 @@ impl<'a> Ref<'a, Unit> {
         self.ucontrol(command);
     }
 
-    pub fn path_find(&self, x: impl Eval<Number>, y: impl Eval<Number>) {
-        assert_same_core!(self, x, y);
+    pub fn path_find(&self, x: impl Eval<'a, Number>, y: impl Eval<'a, Number>) {
         let command = ucontrol::PathFind {
             x: x.eval().eval(),
             y: y.eval().eval(),
@@ -109,8 +118,7 @@ impl<'a> Ref<'a, Unit> {
         self.ucontrol(ucontrol::AutoPathFind {});
     }
 
-    pub fn boost(&self, enable: impl Eval<Number>) {
-        assert_same_core!(self, enable);
+    pub fn boost(&self, enable: impl Eval<'a, Bool>) {
         let command = ucontrol::Boost {
             enable: enable.eval().eval(),
         };
@@ -118,8 +126,12 @@ impl<'a> Ref<'a, Unit> {
     }
 
     #[doc(alias = "shoot")]
-    pub fn target(&self, x: impl Eval<Number>, y: impl Eval<Number>, shoot: impl Eval<Number>) {
-        assert_same_core!(self, x, y, shoot);
+    pub fn target(
+        &self,
+        x: impl Eval<'a, Number>,
+        y: impl Eval<'a, Number>,
+        shoot: impl Eval<'a, Bool>,
+    ) {
         let command = ucontrol::Target {
             x: x.eval().eval(),
             y: y.eval().eval(),
@@ -129,17 +141,19 @@ impl<'a> Ref<'a, Unit> {
     }
 
     #[doc(alias = "shootp")]
-    pub fn targetp<At: Target>(&self, at: impl Eval<At>, shoot: impl Eval<Number>) {
-        assert_same_core!(self, at, shoot);
+    pub fn targetp<At: Target>(&self, at: Ref<'a, At>, shoot: impl Eval<'a, Bool>) {
         let command = ucontrol::Targetp {
-            unit: at.eval().eval(),
+            unit: at.eval(),
             shoot: shoot.eval().eval(),
         };
         self.ucontrol(command);
     }
 
-    pub fn item_drop<B: Buildings>(&self, to: impl Eval<Building<B>>, amount: impl Eval<Number>) {
-        assert_same_core!(self, to, amount);
+    pub fn item_drop<B: Buildings>(
+        &self,
+        to: impl Eval<'a, Building<B>>,
+        amount: impl Eval<'a, Number>,
+    ) {
         let command = ucontrol::ItemDrop {
             to: to.eval().eval(),
             amount: amount.eval().eval(),
@@ -149,14 +163,13 @@ impl<'a> Ref<'a, Unit> {
 
     pub fn item_take<B: Buildings>(
         &self,
-        from: impl Eval<Building<B>>,
-        item: impl Eval<String>,
-        amount: impl Eval<Number>,
+        from: impl Eval<'a, Building<B>>,
+        item: impl Eval<'a, Item>,
+        amount: impl Eval<'a, Number>,
     ) {
-        assert_same_core!(self, from, item, amount);
         let command = ucontrol::ItemTake {
             from: from.eval().eval(),
-            item: item.eval(),
+            item: item.eval().eval(),
             amount: amount.eval().eval(),
         };
         self.ucontrol(command);
@@ -167,8 +180,7 @@ impl<'a> Ref<'a, Unit> {
         self.ucontrol(command);
     }
 
-    pub fn pay_take(&self, take_units: impl Eval<Number>) {
-        assert_same_core!(self, take_units);
+    pub fn pay_take(&self, take_units: impl Eval<'a, Number>) {
         let command = ucontrol::PayTake {
             take_units: take_units.eval().eval(),
         };
@@ -180,8 +192,7 @@ impl<'a> Ref<'a, Unit> {
         self.ucontrol(command);
     }
 
-    pub fn mine(&self, x: impl Eval<Number>, y: impl Eval<Number>) {
-        assert_same_core!(self, x, y);
+    pub fn mine(&self, x: impl Eval<'a, Number>, y: impl Eval<'a, Number>) {
         let command = ucontrol::Mine {
             x: x.eval().eval(),
             y: y.eval().eval(),
@@ -189,25 +200,19 @@ impl<'a> Ref<'a, Unit> {
         self.ucontrol(command);
     }
 
-    pub fn flag(&self, value: impl Eval<Number>) {
-        assert_same_core!(self, value);
+    pub fn flag(&self, value: impl Eval<'a, Number>) {
         let command = ucontrol::Flag {
             value: value.eval().eval(),
         };
         self.ucontrol(command);
     }
 
-    // build
-
-    // getblock
-
     pub fn within(
         &self,
-        x: impl Eval<Number>,
-        y: impl Eval<Number>,
-        radius: impl Eval<Number>,
-    ) -> Ref<'_, Number> {
-        assert_same_core!(self, x, y, radius);
+        x: impl Eval<'a, Number>,
+        y: impl Eval<'a, Number>,
+        radius: impl Eval<'a, Number>,
+    ) -> Ref<'a, Bool> {
         let result = self.core.new_unnamed();
         let command = ucontrol::Within {
             x: x.eval().eval(),
@@ -225,6 +230,69 @@ impl<'a> Ref<'a, Unit> {
     }
 }
 
+/// the content at a position, from [`Ref::get_block`]
+#[derive(Clone, Copy)]
+pub struct Tile<'a> {
+    /// the block, `@air` when there is none
+    pub ty: Ref<'a, Block>,
+    /// `null` when there is no building
+    pub building: Ref<'a, Building>,
+    pub floor: Ref<'a, Block>,
+}
+
+impl<'a, U: Units + Build> Ref<'a, Unit<U>> {
+    /// build `block` at `x`, `y`, facing `rotation` from `0` (right) to `3` (down), and set to
+    /// `config` once built, like the item of a sorter. `()` builds without config, a
+    /// [`Number`] sets blocks configured by a number
+    ///
+    /// the bound unit builds, and `self` is only used to check that it can
+    ///
+    /// ```compile_fail
+    /// # use mlcg::{processor::Processor, types::{content::{Block, Item}, unit::Dagger}};
+    /// Processor::scope(|core| {
+    ///     let dagger = core.unit().cast_unit(Dagger);
+    ///     dagger.build(10, 10, Block::Sorter, 0, Item::Copper);
+    /// });
+    /// ```
+    pub fn build(
+        &self,
+        x: impl Eval<'a, Number>,
+        y: impl Eval<'a, Number>,
+        block: impl Eval<'a, Block>,
+        rotation: impl Eval<'a, Number>,
+        config: impl Eval<'a, Content>,
+    ) {
+        let command = ucontrol::Build {
+            x: x.eval().eval(),
+            y: y.eval().eval(),
+            block: block.eval().eval(),
+            rotation: rotation.eval().eval(),
+            config: config.eval().eval(),
+        };
+        self.ucontrol(command);
+    }
+
+    /// the block, building and floor at `x`, `y`, in range of the bound unit
+    #[doc(alias = "getBlock")]
+    pub fn get_block(&self, x: impl Eval<'a, Number>, y: impl Eval<'a, Number>) -> Tile<'a> {
+        let core = self.core;
+        let tile = Tile {
+            ty: core.new_unnamed(),
+            building: core.new_unnamed(),
+            floor: core.new_unnamed(),
+        };
+        let command = ucontrol::GetBlock {
+            x: x.eval().eval(),
+            y: y.eval().eval(),
+            ty: tile.ty.eval(),
+            building: tile.building.eval(),
+            floor: tile.floor.eval(),
+        };
+        self.ucontrol(command);
+        tile
+    }
+}
+
 pub trait Land: Units + Sepro {}
 pub trait Air: Units + Sepro {}
 pub trait Naval: Units + Sepro {}
@@ -274,7 +342,7 @@ macro_rules! units {
 pub type AnyUnit = Binding;
 
 units! {
-    Binding  => "unit"     : Sepro, Erekir, Land, Air, Naval, Support, Legs, Tank, Flying, Neoplasm, Core, Internal && Shoot, Boost;
+    Binding  => "unit"     : Sepro, Erekir, Land, Air, Naval, Support, Legs, Tank, Flying, Neoplasm, Core, Internal && Shoot, Boost, Build;
 
     Dagger   => "dagger"   : Sepro, Land, Attack && Shoot;
     Mace     => "mace"     : Sepro, Land, Attack && Shoot;
@@ -282,9 +350,50 @@ units! {
     Scepter  => "scepter"  : Sepro, Land, Attack && Shoot;
     Reign    => "reign"    : Sepro, Land, Attack && Shoot;
 
-    Nova     => "nova"     : Sepro, Land, Support && Shoot, Boost;
-    Pulsar   => "pulsar"   : Sepro, Land, Support && Shoot, Boost;
-    Quasar   => "quasar"   : Sepro, Land, Support && Shoot, Boost;
+    Nova     => "nova"     : Sepro, Land, Support && Shoot, Boost, Build;
+    Pulsar   => "pulsar"   : Sepro, Land, Support && Shoot, Boost, Build;
+    Quasar   => "quasar"   : Sepro, Land, Support && Shoot, Boost, Build;
     Vela     => "vela"     : Sepro, Land, Support && Shoot, Boost;
     Corvus   => "corvus"   : Sepro, Land, Support && Shoot, Boost;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebuild() {
+        Processor::scope(|core| {
+            let nova = core.unit_bind::<Nova>().cast_unit(Nova);
+            let tile = nova.get_block(10, 20);
+            core.if_(tile.ty.eq(Block::Air), |_| {
+                nova.build(10, 20, Block::Sorter, 0, Item::Copper);
+            })
+            .else_(|_| core.unit().stop());
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "ubind @nova\n\
+                 ucontrol getBlock 10 20 v0 v1 v2\n\
+                 jump 5 notEqual v0 @air\n\
+                 ucontrol build 10 20 @sorter 0 @copper\n\
+                 jump 0 always 0 0\n\
+                 ucontrol stop 0 0 0 0 0\n"
+            );
+        });
+
+        Processor::scope(|core| {
+            let nova = core.unit_bind::<Nova>().cast_unit(Nova);
+            let plan = core.from_mdt::<Number>("plan");
+            nova.build(10, 20, Block::CopperWall, 0, ());
+            nova.build(11, 20, Block::GroundFactory, 0, plan);
+
+            assert_eq!(
+                core.generate().unwrap(),
+                "ubind @nova\n\
+                 ucontrol build 10 20 @copper-wall 0 null\n\
+                 ucontrol build 11 20 @ground-factory 0 plan\n"
+            );
+        });
+    }
+}